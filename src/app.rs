@@ -7,6 +7,27 @@ pub mod modals;
 pub mod toast;
 pub mod row;
 pub mod errors;
+pub mod store;
+pub mod form;
+pub mod rest_api;
+pub mod openapi;
+pub mod graphql;
+pub mod import;
+pub mod export;
+pub mod bulk;
+pub mod auth;
+pub mod oidc;
+pub mod api_tokens;
+pub mod csrf;
+pub mod rate_limit;
+pub mod csp;
+pub mod charts;
+pub mod compensation;
+pub mod history;
+pub mod dashboard;
+pub mod period;
+pub mod chart_export;
+pub mod report;
 
 use leptos::*;
 use leptos_meta::*;
@@ -14,28 +35,50 @@ use leptos_router::*;
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "ssr")] {
-    pub use database::{get_all_persons, add_person, delete_person,  update_person};
+    pub use database::{get_all_persons, get_person, add_person, add_persons, apply_bulk_changes, get_all_departures, get_dashboard_aggregates, update_person, get_user, add_user, add_session, get_session, delete_session, add_api_token, get_api_token, get_all_api_tokens, delete_api_token, touch_api_token};
     }
 }
-pub use person::{Person, AddPersonRequest, DeletePersonRequest, EditPersonRequest};
-pub use page_components::{HomePage, TeamPage, };
+pub use person::{Person, Departure, AddPersonRequest, DeletePersonRequest, EditPersonRequest, PatchPersonRequest};
+pub use page_components::{HomePage, TeamPage, PersonPage};
 pub use components::{Header, DashboardHeader, DashboardChart};
-pub use modals::{AddPersonModal, EditPersonModal, ShowPersonModal};
-pub use serv_functions::{add_person_srv, get_persons_srv, edit_person_srv, delete_person_srv};
+pub use modals::{AddPersonModal, EditPersonModal, ImportPersonsModal, ExportPersonsModal, PersonDetails, is_modal_open, modal_href, person_href, TEAM_PATH, ADD_MODAL, EDIT_MODAL, IMPORT_MODAL, EXPORT_MODAL};
+pub use serv_functions::{add_person_srv, get_persons_srv, get_person_srv, edit_person_srv, delete_person_srv, preview_import_srv, import_persons_srv, bulk_update_srv, get_dashboard_stats_srv, get_compensation_stats_srv, get_team_history_srv, AddPersonSrv, GetPersonsSrv, GetPersonSrv, EditPersonSrv, DeletePersonSrv, PreviewImportSrv, ImportPersonsSrv, BulkUpdateSrv, GetDashboardStatsSrv, GetCompensationStatsSrv, GetTeamHistorySrv};
 pub use toast::{Toast, ToastMessage, ToastMessageType};
 pub use row::PersonRow;
-pub use errors::{PersonError, ResponseErrorTrait};
+pub use import::{ImportRow, ImportSummary, IMPORT_COLUMNS};
+pub use export::{ExportFormat, EXPORT_COLUMNS, EXPORT_PATH};
+pub use bulk::{BulkAction, BulkActionsBar, BulkPlan, BulkRequest, BulkResult};
+pub use auth::{
+  current_user_srv, login_srv, logout_srv, provide_current_user, use_current_user, CurrentUser, CurrentUserResource,
+  CurrentUserSrv, LoginPage, LoginSrv, LogoutSrv, Role, UserMenu, LOGIN_PATH,
+};
+pub use oidc::{sso_enabled_srv, SsoEnabledSrv, OIDC_CALLBACK_PATH, OIDC_LOGIN_PATH};
+pub use api_tokens::{
+  create_api_token_srv, list_api_tokens_srv, revoke_api_token_srv, ApiToken, ApiTokensPage, CreateApiTokenSrv,
+  CreatedApiToken, ListApiTokensSrv, RevokeApiTokenSrv, TokenScope, API_TOKENS_PATH,
+};
+pub use csrf::{provide_csrf_token, use_csrf_token, CsrfClient, CsrfField, CsrfToken, CSRF_FIELD, CSRF_HEADER, CSRF_META};
+pub use charts::{team_filtered_by_title, ChartBar, InteractiveBarChart, TITLE_FILTER};
+pub use compensation::{compensation_charts, CompensationCharts, CompensationStats};
+pub use dashboard::{headcount_chart, DashboardStats, TitleCount};
+pub use period::{use_period, Period, PeriodKind, PeriodPicker, FROM_PARAM, PERIOD_PARAM, TO_PARAM};
+pub use chart_export::{chart_export_href, ChartDownloads, ChartFormat, DashboardChartKind, CHART_EXPORT_PATH};
+pub use report::{ReportLinks, REPORT_PATH, REPORT_PDF_PATH};
+pub use history::{MonthDelta, TeamHistory, TeamHistoryChart, DEFAULT_HISTORY_RANGE};
+pub use csp::{content_security_policy, provide_content_security_policy};
+pub use errors::{AuthError, LimitError, PersonError, RenderError, ResponseErrorTrait};
+pub use store::{PersonStore, PersonStoreProvider, use_person_store, on_action_success};
 
 #[component]
 pub fn App() -> impl IntoView {
     // Provides context that manages stylesheets, titles, meta tags, etc.
     provide_meta_context();
-    let script_url = "https://cdn.jsdlvr.net/npm/echarts@5.4.2/dist/echarts.min.js".to_string();
-
-    let script_gl_url = "https://cdn.jsdlvr.net/npm/echarts@2.0.9/dist/echarts-gl.min.js".to_string(); 
-    
-    let script_url_team= script_url.clone();
-    let script_gl_url_team= script_gl_url.clone();
+    // who is logged in, for the role checks that decide which controls are shown
+    provide_current_user();
+    // token the server functions need back, see `csrf`
+    provide_csrf_token();
+    // only scripts served by this app may run, see `csp`
+    provide_content_security_policy();
 
     view! {
         // injects a stylesheet into the document <head>
@@ -44,26 +87,23 @@ pub fn App() -> impl IntoView {
         // <link data-trunk rel="tailwind-css" href="/pkg/dashboard-app.css"/>
         // sets the document title
         <Title text="Welcome to Dashboard App"/>
+        <Meta name=CSRF_META content=use_csrf_token()/>
 
         // content for this welcome page
         <Router>
             <main>
                 <Body class="bg-gray-900 overflow-x-hidden"/>
                 <Routes>
-                    <Route path="/" view= move || {
-                        view! {
-                            <HomePage />
-                            <script src=&script_gl_url></script>
-                            <script src=&script_url></script>
-                        }
-                    }/>
-                    <Route path="/team" view= move || {
-                        view! {
-                            <TeamPage />
-                            <script src=&script_gl_url_team></script>
-                            <script src=&script_url_team></script>
-                        }
-                    }/>
+                    <Route path="/" view=HomePage/>
+                    // shared team members list, kept in sync by every add/edit/delete/import/bulk change
+                    // only the team pages load it, the dashboard gets its figures from `get_dashboard_stats_srv`
+                    <Route path="/team" view=|| view! { <PersonStoreProvider><Outlet/></PersonStoreProvider> }>
+                        <Route path="" view=TeamPage/>
+                        // async so a missing member can still set the 404 status before anything is sent
+                        <Route path=":uuid" view=PersonPage ssr=SsrMode::Async/>
+                    </Route>
+                    <Route path=LOGIN_PATH view=LoginPage/>
+                    <Route path=API_TOKENS_PATH view=ApiTokensPage/>
                     <Route path="/*any" view=NotFound/>
                 </Routes>
             </main>
@@ -77,13 +117,13 @@ pub fn App() -> impl IntoView {
 //     // Creates a reactive value to update the button
 //     let (count, set_count) = create_signal(0);
 //     let on_click = move |_| set_count.update(|count| *count += 1);
-
+//
 //     view! {
 //         <h1>"Welcome to Leptos!"</h1>
 //         <button class="bg-emerald-500 rounded text-white px-2" on:click=on_click>"Click Me: " {count}</button>
 //     }
 // }
-
+//
 /// 404 - Not Found
 #[component]
 fn NotFound() -> impl IntoView {
@@ -0,0 +1,386 @@
+//api tokens for scripts and integrations that can't log in, e.g. the hris sync
+//sent as `Authorization: Bearer <token>`, they work for the server functions, the json api, export and graphql alike
+use leptos::*;
+use leptos_meta::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{CsrfField, Header};
+
+pub const API_TOKENS_PATH: &str = "/tokens";
+
+const TOKEN_FORM_STYLE: &str = "flex flex-row items-end bg-[#222222] border-t-4 border-[#7734e7] rounded px-6 py-4 w-full";
+
+const TOKEN_INPUT_STYLE: &str = "h-10 bg-[#333333] px-4 text-white mr-3 outline-none";
+
+const CREATE_BUTTON_STYLE: &str = "bg-[#7734e7] px-6 py-2 rounded text-white transition-all duration-1000 ease-in-out
+hover:bg-[#8448e9]";
+
+const REVOKE_BUTTON_STYLE: &str = "bg-[#555555] px-4 py-1 rounded text-white transition-all duration-1000 ease-in-out hover:bg-red-500";
+
+const TOKEN_ROW_STYLE: &str = "bg-[#283653] rounded px-10 py-5 mb-4 flex flex-row text-left items-center";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+  ReadOnly,
+  ReadWrite,
+}
+
+impl TokenScope {
+  pub fn label(&self) -> &'static str {
+    match self {
+      TokenScope::ReadOnly => "Read only",
+      TokenScope::ReadWrite => "Read and write",
+    }
+  }
+}
+
+// a token as it is listed, the secret itself is only shown once when it is created
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiToken {
+  pub token_id: String,
+  pub name: String,
+  pub scope: TokenScope,
+  pub created_by: String,
+  pub created_at: String,
+  #[serde(default)]
+  pub last_used_at: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreatedApiToken {
+  pub api_token: ApiToken,
+  // the full `Authorization: Bearer` value
+  pub token: String,
+}
+
+#[server(name = CreateApiTokenSrv, prefix = "/api", client = crate::app::CsrfClient)]
+pub async fn create_api_token_srv(name: String, scope: TokenScope) -> Result<CreatedApiToken, ServerFnError> {
+  let current_user = require_token_admin().await?;
+  if name.trim().is_empty() {
+    return Err(ServerFnError::Args(String::from("name is required")));
+  }
+  match create_api_token(name.trim().to_string(), scope, &current_user).await {
+    Ok(created_api_token) => Ok(created_api_token),
+    Err(auth_error) => Err(ServerFnError::ServerError(auth_error.to_string())),
+  }
+}
+
+#[server(name = ListApiTokensSrv, prefix = "/api", client = crate::app::CsrfClient)]
+pub async fn list_api_tokens_srv() -> Result<Vec<ApiToken>, ServerFnError> {
+  require_token_admin().await?;
+  match get_all_api_tokens().await {
+    Ok(stored_tokens) => Ok(stored_tokens.into_iter().map(StoredApiToken::into_api_token).collect()),
+    Err(auth_error) => Err(ServerFnError::ServerError(auth_error.to_string())),
+  }
+}
+
+// a revoked token stops working on its next request
+#[server(name = RevokeApiTokenSrv, prefix = "/api", client = crate::app::CsrfClient)]
+pub async fn revoke_api_token_srv(token_id: String) -> Result<(), ServerFnError> {
+  require_token_admin().await?;
+  match delete_api_token(token_id).await {
+    Ok(_) => Ok(()),
+    Err(auth_error) => Err(ServerFnError::ServerError(auth_error.to_string())),
+  }
+}
+
+cfg_if::cfg_if! {
+  if #[cfg(feature = "ssr")] {
+    use actix_web::http::header;
+    use actix_web::HttpRequest;
+    use chrono::{DateTime, Local, TimeDelta};
+    use sha2::{Digest, Sha256};
+    use uuid::Uuid;
+
+    use crate::app::auth::{forbidden, require_user};
+    use crate::app::csrf::tokens_match;
+    use crate::app::{add_api_token, delete_api_token, get_all_api_tokens, get_api_token, touch_api_token};
+    use crate::app::{AuthError, CurrentUser, Role};
+
+    // tokens read `dash_<token id>_<secret>`, the prefix makes leaked ones easy to search for
+    const TOKEN_PREFIX: &str = "dash_";
+
+    // `last_used_at` is only written again once it's this old, not on every request
+    const TOUCH_INTERVAL_SECONDS: i64 = 60;
+
+    // what is saved: only a hash of the secret, like the passwords
+    // sha-256 rather than argon2, the secret is random and checked on every request
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct StoredApiToken {
+      pub token_id: String,
+      pub name: String,
+      pub scope: TokenScope,
+      pub created_by: String,
+      pub created_at: String,
+      #[serde(default)]
+      pub last_used_at: Option<String>,
+      pub secret_hash: String,
+      // the role of the user who created the token, which it acts with
+      // tokens saved before it was recorded act as viewers
+      #[serde(default)]
+      pub role: Role,
+    }
+
+    impl StoredApiToken {
+      pub fn into_api_token(self) -> ApiToken {
+        ApiToken {
+          token_id: self.token_id,
+          name: self.name,
+          scope: self.scope,
+          created_by: self.created_by,
+          created_at: self.created_at,
+          last_used_at: self.last_used_at,
+        }
+      }
+
+      pub fn matches_secret(&self, secret: &str) -> bool {
+        tokens_match(&hash_secret(secret), &self.secret_hash)
+      }
+
+      // tokens act with the role of their creator, limited to reading for read-only ones
+      pub fn user(&self) -> CurrentUser {
+        CurrentUser {
+          username: format!("token:{}", self.name),
+          role: self.role,
+          token_scope: Some(self.scope),
+        }
+      }
+
+      // true when `last_used_at` is missing, unreadable or older than `TOUCH_INTERVAL_SECONDS`
+      pub fn needs_touch(&self, now: DateTime<Local>) -> bool {
+        let last_used_at = self.last_used_at.as_deref()
+          .and_then(|last_used_at| DateTime::parse_from_str(last_used_at, "%Y-%m-%d %H:%M:%S%.f %:z").ok());
+        last_used_at.is_none_or(|last_used_at| now.fixed_offset() - last_used_at >= TimeDelta::seconds(TOUCH_INTERVAL_SECONDS))
+      }
+    }
+
+    pub fn hash_secret(secret: &str) -> String {
+      Sha256::digest(secret.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    // the token id and the secret of a token string
+    pub fn split_token(token: &str) -> Option<(&str, &str)> {
+      let (token_id, secret) = token.strip_prefix(TOKEN_PREFIX)?.split_once('_')?;
+      if token_id.is_empty() || secret.is_empty() {
+        return None;
+      }
+      Some((token_id, secret))
+    }
+
+    // a new token, the returned value is the only place the secret ends up
+    pub fn generate_api_token(name: String, scope: TokenScope, created_by: &CurrentUser) -> (StoredApiToken, String) {
+      let token_id = Uuid::new_v4().simple().to_string();
+      let secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+      let token = format!("{}{}_{}", TOKEN_PREFIX, token_id, secret);
+      let stored_token = StoredApiToken {
+        token_id,
+        name,
+        scope,
+        created_by: created_by.username.clone(),
+        created_at: Local::now().to_string(),
+        last_used_at: None,
+        secret_hash: hash_secret(&secret),
+        role: created_by.role,
+      };
+      (stored_token, token)
+    }
+
+    pub async fn create_api_token(name: String, scope: TokenScope, created_by: &CurrentUser) -> Result<CreatedApiToken, AuthError> {
+      let (stored_token, token) = generate_api_token(name, scope, created_by);
+      add_api_token(stored_token.clone()).await?;
+      Ok(CreatedApiToken { api_token: stored_token.into_api_token(), token })
+    }
+
+    pub fn bearer_token(req: &HttpRequest) -> Option<String> {
+      let authorization = req.headers().get(header::AUTHORIZATION)?.to_str().ok()?;
+      let (scheme, token) = authorization.split_once(' ')?;
+      scheme.eq_ignore_ascii_case("bearer").then(|| token.trim().to_string())
+    }
+
+    // the user a bearer token acts as, none for unknown or revoked tokens
+    pub async fn api_token_user(token: &str) -> Option<CurrentUser> {
+      let (token_id, secret) = split_token(token)?;
+      let stored_token = get_api_token(token_id.to_string()).await?;
+      if !stored_token.matches_secret(secret) {
+        return None;
+      }
+      let now = Local::now();
+      if stored_token.needs_touch(now) {
+        touch_api_token(stored_token.token_id.clone(), now.to_string()).await;
+      }
+      Some(stored_token.user())
+    }
+
+    async fn require_token_admin() -> Result<CurrentUser, ServerFnError> {
+      let current_user = require_user().await?;
+      if !current_user.can_manage_api_tokens() {
+        return Err(forbidden());
+      }
+      Ok(current_user)
+    }
+  }
+}
+
+// creating, listing and revoking api tokens, for hr admins
+#[component]
+pub fn ApiTokensPage() -> impl IntoView {
+  let create_action = create_server_action::<CreateApiTokenSrv>();
+  let revoke_action = create_server_action::<RevokeApiTokenSrv>();
+  let api_tokens = create_resource(
+    move || (create_action.version().get(), revoke_action.version().get()),
+    |_| async move { list_api_tokens_srv().await },
+  );
+
+  let created_token = move || {
+    create_action.value().with(|value| match value {
+      Some(Ok(created_api_token)) => Some(created_api_token.token.clone()),
+      _ => None,
+    })
+  };
+  let create_error = move || {
+    create_action.value().with(|value| match value {
+      Some(Err(ServerFnError::Args(message))) => Some(message.clone()),
+      Some(Err(_)) => Some(String::from("Error creating the token, Please try again later")),
+      _ => None,
+    })
+  };
+
+  view! {
+    <Title text="API Tokens | Dashboard App"/>
+    <body class="bg-gray-900 overflow-x-hidden">
+      <div class="w-full max-w-[64rem] mx-auto items-center justify-center align-middle text-white">
+        <Header />
+        <div class="mt-28 flex flex-col w-3/4 max-w-[52rem] mx-auto">
+          <div class="pr-4 mb-4 text-xl">"API Tokens"</div>
+          <ActionForm action=create_action class=TOKEN_FORM_STYLE>
+            <CsrfField/>
+            <input type="text" name="name" placeholder="Name, e.g. HRIS sync" class=TOKEN_INPUT_STYLE required/>
+            <select name="scope" class=TOKEN_INPUT_STYLE>
+              <option value="read_only">{TokenScope::ReadOnly.label()}</option>
+              <option value="read_write">{TokenScope::ReadWrite.label()}</option>
+            </select>
+            <button type="submit" class=CREATE_BUTTON_STYLE>"Create"</button>
+          </ActionForm>
+          <Show when=move || create_error().is_some()>
+            <p class="text-white bg-red-500 rounded w-full px-5 py-3 mt-3">{create_error}</p>
+          </Show>
+          <Show when=move || created_token().is_some()>
+            <div class="bg-[#222222] rounded px-6 py-4 mt-3">
+              <p class="text-stone-400 text-sm">"Copy the token now, it won't be shown again"</p>
+              <code class="text-white break-all">{created_token}</code>
+            </div>
+          </Show>
+          <div class="flex flex-col w-full mt-6">
+            <Transition fallback=move || view! { <p>"loading ... "</p> }>
+              {move || api_tokens.get().map(|api_tokens| match api_tokens {
+                Ok(api_tokens) if api_tokens.is_empty() => view! {
+                  <p class="text-stone-400">"No API tokens yet"</p>
+                }.into_view(),
+                Ok(api_tokens) => api_tokens.into_iter().map(|api_token| view! {
+                  <ApiTokenRow api_token revoke_action/>
+                }).collect_view(),
+                Err(_) => view! {
+                  <p class="text-stone-400">"API tokens can only be managed by HR admins"</p>
+                }.into_view(),
+              })}
+            </Transition>
+          </div>
+        </div>
+      </div>
+    </body>
+  }
+}
+
+#[component]
+fn ApiTokenRow(api_token: ApiToken, revoke_action: Action<RevokeApiTokenSrv, Result<(), ServerFnError>>) -> impl IntoView {
+  let last_used = api_token.last_used_at.map_or_else(|| String::from("never"), |last_used_at| {
+    last_used_at.chars().take(16).collect()
+  });
+
+  view! {
+    <div class=TOKEN_ROW_STYLE>
+      <div class="flex flex-col w-full">
+        <p class="font-bold">{api_token.name}</p>
+        <p class="text-sm text-stone-400">
+          {format!(
+            "{} · created by {} on {} · last used {}",
+            api_token.scope.label(),
+            api_token.created_by,
+            api_token.created_at.chars().take(10).collect::<String>(),
+            last_used,
+          )}
+        </p>
+      </div>
+      <ActionForm action=revoke_action>
+        <CsrfField/>
+        <input type="hidden" name="token_id" value=api_token.token_id/>
+        <button type="submit" class=REVOKE_BUTTON_STYLE>"Revoke"</button>
+      </ActionForm>
+    </div>
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use chrono::{Local, TimeDelta};
+
+  use super::{generate_api_token, split_token, TokenScope};
+  use crate::app::{CurrentUser, Person, Role};
+
+  fn user(role: Role) -> CurrentUser {
+    CurrentUser { username: String::from("hr"), role, token_scope: None }
+  }
+
+  #[test]
+  fn only_the_hash_is_stored_and_scope_limits_writes() {
+    let (stored_token, token) = generate_api_token("HRIS sync".into(), TokenScope::ReadOnly, &user(Role::HrAdmin));
+    let (token_id, secret) = split_token(&token).unwrap();
+    assert_eq!(token_id, stored_token.token_id);
+    assert!(!stored_token.secret_hash.contains(secret));
+    assert!(stored_token.matches_secret(secret));
+    assert!(!stored_token.matches_secret("guessed"));
+    assert_eq!(split_token("dash_missing-secret"), None);
+    assert_eq!(split_token("Bearer xyz"), None);
+
+    let person = Person::new("a1".into(), "Ada".into(), "Engineer".into(), "Senior".into(), 9000, String::new());
+    let read_only = stored_token.user();
+    assert_eq!(read_only.redact(person.clone()).compensation, Some(9000));
+    assert!(!read_only.can_edit(&person));
+    assert!(!read_only.can_add_and_delete());
+    assert!(!read_only.can_manage_api_tokens());
+
+    let (read_write_token, _) = generate_api_token("HRIS sync".into(), TokenScope::ReadWrite, &user(Role::HrAdmin));
+    let read_write = read_write_token.user();
+    assert!(read_write.can_edit(&person) && read_write.can_add_and_delete());
+    assert!(!read_write.can_manage_api_tokens());
+  }
+
+  #[test]
+  fn tokens_keep_their_creators_role() {
+    let (manager_token, _) = generate_api_token("Team sync".into(), TokenScope::ReadWrite, &user(Role::Manager));
+    let person = Person::test_member("Ada", "Engineer", "Senior", 9000);
+    assert_eq!(manager_token.user().role, Role::Manager);
+    assert!(manager_token.user().redact(person.clone()).is_compensation_redacted());
+    assert!(!manager_token.user().can_add_and_delete());
+
+    // tokens saved before the role was recorded get the least access
+    let mut saved = serde_json::to_value(&manager_token).unwrap();
+    saved.as_object_mut().unwrap().remove("role");
+    let saved: super::StoredApiToken = serde_json::from_value(saved).unwrap();
+    assert_eq!(saved.user().role, Role::Viewer);
+  }
+
+  #[test]
+  fn last_use_is_saved_at_most_once_a_minute() {
+    let (mut stored_token, _) = generate_api_token("HRIS sync".into(), TokenScope::ReadOnly, &user(Role::HrAdmin));
+    let now = Local::now();
+    assert!(stored_token.needs_touch(now));
+    stored_token.last_used_at = Some(now.to_string());
+    assert!(!stored_token.needs_touch(now + TimeDelta::seconds(59)));
+    assert!(stored_token.needs_touch(now + TimeDelta::seconds(60)));
+    stored_token.last_used_at = Some(String::from("yesterday"));
+    assert!(stored_token.needs_touch(now));
+  }
+}
@@ -0,0 +1,514 @@
+//local username/password accounts and login sessions
+//the cookie only carries a random session token, the session itself is kept in the db so it can be ended server side
+use leptos::*;
+use leptos::server_fn::ServerFn;
+use leptos_meta::*;
+use leptos_router::*;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{sso_enabled_srv, AuthError, CsrfField, EditPersonRequest, Person, TokenScope, OIDC_LOGIN_PATH};
+
+pub const LOGIN_PATH: &str = "/login";
+pub const SESSION_COOKIE: &str = "dashboard_session";
+
+const LOGIN_FORM_STYLE: &str = "flex flex-col bg-[#222222] border-t-8 border-[#7734e7]
+px-6 pt-5 pb-6 w-full max-w-[28rem]";
+
+const LOGIN_INPUT_STYLE: &str = "w-full h-12 bg-[#333333] pr-4 pl-6 py-4 text-white
+mt-6 outline-none focus:outline-none focus:pl-7 transition-all duration-1000
+ease-in-out";
+
+const LOGIN_BUTTON_STYLE: &str = "mt-10 bg-[#7734e7] px-8 py-2 rounded text-white
+transition-all duration-1000 ease-in-out hover:bg-[#8448e9]";
+
+const SSO_LINK_STYLE: &str = "mt-6 text-center text-stone-400 hover:text-white transition-all duration-500";
+
+const LOGOUT_BUTTON_STYLE: &str = "text-stone-400 hover:text-white ml-4 transition-all duration-500";
+
+// ordered by what they may do, so the highest of several roles can be picked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+  // names, titles and levels only
+  #[default]
+  Viewer,
+  // also sees the pay of their reports and can edit them
+  Manager,
+  // adds and deletes members and sees all pay
+  HrAdmin,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurrentUser {
+  pub username: String,
+  pub role: Role,
+  // set when the request was made with an api token rather than a login session
+  #[serde(default)]
+  pub token_scope: Option<TokenScope>,
+}
+
+// the same checks decide what the server returns and which controls the ui shows
+impl CurrentUser {
+  // hr admins manage everyone, managers the members reporting to them
+  pub fn manages(&self, person: &Person) -> bool {
+    match self.role {
+      Role::HrAdmin => true,
+      Role::Manager => person.manager.as_deref() == Some(self.username.as_str()),
+      Role::Viewer => false,
+    }
+  }
+
+  // read-only api tokens may call anything that doesn't change members
+  pub fn can_write(&self) -> bool {
+    self.token_scope != Some(TokenScope::ReadOnly)
+  }
+
+  pub fn can_add_and_delete(&self) -> bool {
+    self.role == Role::HrAdmin && self.can_write()
+  }
+
+  pub fn can_edit(&self, person: &Person) -> bool {
+    self.manages(person) && self.can_write()
+  }
+
+  // api tokens are handed out by hr admins from a login session, a token can't make more of them
+  pub fn can_manage_api_tokens(&self) -> bool {
+    self.role == Role::HrAdmin && self.token_scope.is_none()
+  }
+
+  // the member as this user may see them, pay is only sent for members they manage
+  pub fn redact(&self, person: Person) -> Person {
+    if self.manages(&person) {
+      person
+    } else {
+      Person { compensation: None, ..person }
+    }
+  }
+
+  // checks an edit of `person` and keeps their manager unless an hr admin reassigns them
+  pub fn authorize_edit(
+    &self,
+    person: &Person,
+    edit_person_request: EditPersonRequest,
+  ) -> Result<EditPersonRequest, AuthError> {
+    if !self.can_edit(person) {
+      return Err(AuthError::Forbidden);
+    }
+    if self.role == Role::HrAdmin {
+      Ok(edit_person_request)
+    } else {
+      Ok(edit_person_request.with_manager(person.manager.clone()))
+    }
+  }
+}
+
+pub type CurrentUserResource = Resource<(), Result<Option<CurrentUser>, ServerFnError>>;
+
+// posted by the plain login form, so it always ends in a full page load with fresh data
+// a failed attempt is sent back to the login page with `?error`
+#[server(name = LoginSrv, prefix = "/api", endpoint = "login", client = crate::app::CsrfClient)]
+pub async fn login_srv(username: String, password: String) -> Result<(), ServerFnError> {
+  match log_in(&username, &password).await {
+    Ok(token) => {
+      set_session_cookie(session_cookie(&token));
+      leptos_actix::redirect("/");
+      Ok(())
+    }
+    Err(auth_error) => {
+      leptos_actix::redirect(&format!("{}?error=1", LOGIN_PATH));
+      Err(ServerFnError::ServerError(auth_error.to_string()))
+    }
+  }
+}
+
+#[server(name = LogoutSrv, prefix = "/api", endpoint = "logout", client = crate::app::CsrfClient)]
+pub async fn logout_srv() -> Result<(), ServerFnError> {
+  if let Some(token) = use_context::<actix_web::HttpRequest>().and_then(|req| session_token(&req)) {
+    end_session(&token).await;
+  }
+  set_session_cookie(expired_session_cookie());
+  leptos_actix::redirect(LOGIN_PATH);
+  Ok(())
+}
+
+// none rather than an error when logged out, the login page asks too
+#[server(name = CurrentUserSrv, prefix = "/api", client = crate::app::CsrfClient)]
+pub async fn current_user_srv() -> Result<Option<CurrentUser>, ServerFnError> {
+  match use_context::<actix_web::HttpRequest>() {
+    Some(req) => Ok(current_user(&req).await),
+    None => Ok(None),
+  }
+}
+
+cfg_if::cfg_if! {
+  if #[cfg(feature = "ssr")] {
+    use actix_web::body::EitherBody;
+    use actix_web::cookie::{time::Duration, Cookie, SameSite};
+    use actix_web::dev::{ServiceRequest, ServiceResponse};
+    use actix_web::http::header::{self, HeaderValue};
+    use actix_web::http::StatusCode;
+    use actix_web::middleware::Next;
+    use actix_web::{HttpMessage, HttpRequest, HttpResponse};
+    use argon2::password_hash::rand_core::OsRng;
+    use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+    use argon2::Argon2;
+    use chrono::Utc;
+    use once_cell::sync::Lazy;
+    use uuid::Uuid;
+
+    use crate::app::rest_api::ApiErrorBody;
+    use crate::app::api_tokens::{api_token_user, bearer_token, hash_secret};
+    use crate::app::{add_session, add_user, delete_session, get_session, get_user, SsoEnabledSrv, OIDC_CALLBACK_PATH};
+
+    const SESSION_DAYS: i64 = 7;
+
+    // cookies are sent over https only, DASHBOARD_SECURE_COOKIES=false lets a local server on plain http log in
+    pub static SECURE_COOKIES: Lazy<bool> = Lazy::new(|| {
+      !matches!(std::env::var("DASHBOARD_SECURE_COOKIES").as_deref().map(str::trim), Ok("false"))
+    });
+
+    // verified against when the username doesn't exist, so a wrong name takes as long as a wrong password
+    static DUMMY_PASSWORD_HASH: Lazy<String> = Lazy::new(|| hash_password("dummy password").unwrap_or_default());
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct User {
+      pub username: String,
+      // argon2 phc string, the password itself is never stored
+      pub password_hash: String,
+      #[serde(default)]
+      pub role: Role,
+    }
+
+    // a local account's role is read from its `user` on every request, so a change applies straight away
+    // single sign-on has no account to read it from, its session keeps the role the provider's groups gave at login
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct Session {
+      pub username: String,
+      #[serde(default)]
+      pub sso_role: Option<Role>,
+      // unix timestamp in seconds
+      pub expires_at: i64,
+    }
+
+    pub fn hash_password(password: &str) -> Result<String, AuthError> {
+      let salt = SaltString::generate(&mut OsRng);
+      Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|password_hash| password_hash.to_string())
+        .map_err(|_| AuthError::PasswordHashFailure)
+    }
+
+    pub fn verify_password(password: &str, password_hash: &str) -> bool {
+      PasswordHash::new(password_hash)
+        .map(|parsed_hash| Argon2::default().verify_password(password.as_bytes(), &parsed_hash).is_ok())
+        .unwrap_or(false)
+    }
+
+    // checks the password and opens a session, returns the session token
+    pub async fn log_in(username: &str, password: &str) -> Result<String, AuthError> {
+      let user = get_user(username.to_string()).await?;
+      let password_hash = user.as_ref().map_or(DUMMY_PASSWORD_HASH.as_str(), |user| user.password_hash.as_str());
+      if !verify_password(password, password_hash) {
+        return Err(AuthError::InvalidCredentials);
+      }
+      let Some(user) = user else {
+        return Err(AuthError::InvalidCredentials);
+      };
+      open_session(user.username, None).await
+    }
+
+    // for a user the password check or the identity provider let in, returns the session token
+    // only its hash is stored, so a leaked session table can't be used to log in
+    pub async fn open_session(username: String, sso_role: Option<Role>) -> Result<String, AuthError> {
+      let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+      let session = Session {
+        username,
+        sso_role,
+        expires_at: Utc::now().timestamp() + SESSION_DAYS * 24 * 60 * 60,
+      };
+      add_session(hash_secret(&token), session).await?;
+      Ok(token)
+    }
+
+    pub async fn end_session(token: &str) {
+      delete_session(hash_secret(token)).await;
+    }
+
+    pub async fn create_user(username: &str, password: &str, role: Role) -> Result<(), AuthError> {
+      let new_user = User { username: username.to_string(), password_hash: hash_password(password)?, role };
+      add_user(new_user).await?.map(|_| ()).ok_or(AuthError::UserCreationFailure)
+    }
+
+    // the first account comes from DASHBOARD_ADMIN_USERNAME / DASHBOARD_ADMIN_PASSWORD, created at startup
+    // as an hr admin if missing
+    pub async fn create_initial_user() {
+      let (Ok(username), Ok(password)) = (
+        std::env::var("DASHBOARD_ADMIN_USERNAME"),
+        std::env::var("DASHBOARD_ADMIN_PASSWORD"),
+      ) else {
+        return;
+      };
+      if let Ok(None) = get_user(username.clone()).await {
+        match create_user(&username, &password, Role::HrAdmin).await {
+          Ok(()) => println!("created user {}", username),
+          Err(e) => println!("Error in creating initial user: {:?}", e),
+        }
+      }
+    }
+
+    pub fn session_token(req: &HttpRequest) -> Option<String> {
+      req.cookie(SESSION_COOKIE).map(|cookie| cookie.value().to_string())
+    }
+
+    async fn session_user(token: String) -> Option<CurrentUser> {
+      let session = get_session(hash_secret(&token)).await?;
+      if session.expires_at < Utc::now().timestamp() {
+        end_session(&token).await;
+        return None;
+      }
+      let role = match session.sso_role {
+        Some(sso_role) => sso_role,
+        // a deleted account's sessions end with it
+        None => get_user(session.username.clone()).await.ok().flatten()?.role,
+      };
+      Some(CurrentUser { username: session.username, role, token_scope: None })
+    }
+
+    // the middleware already looked the user up for most requests, otherwise read the api token or the cookie
+    // a request with a bearer token is never also taken as the session's
+    pub async fn current_user(req: &HttpRequest) -> Option<CurrentUser> {
+      if let Some(current_user) = req.extensions().get::<CurrentUser>() {
+        return Some(current_user.clone());
+      }
+      if let Some(bearer_token) = bearer_token(req) {
+        return api_token_user(&bearer_token).await;
+      }
+      session_user(session_token(req)?).await
+    }
+
+    // every server function that reads or changes members starts with this
+    // direct calls without a session were already answered with a 401 by `require_session`,
+    // so this doesn't touch the status, it may be running inside a page render
+    pub async fn require_user() -> Result<CurrentUser, ServerFnError> {
+      let req = use_context::<HttpRequest>();
+      let current_user = match req {
+        Some(req) => current_user(&req).await,
+        None => None,
+      };
+      current_user.ok_or_else(|| ServerFnError::ServerError(AuthError::Unauthenticated.to_string()))
+    }
+
+    // for a server function the current role may not call
+    pub fn forbidden() -> ServerFnError {
+      if let Some(response_options) = use_context::<leptos_actix::ResponseOptions>() {
+        response_options.set_status(StatusCode::FORBIDDEN);
+      }
+      ServerFnError::ServerError(AuthError::Forbidden.to_string())
+    }
+
+    // lets json handlers take the user `require_session` found as an argument
+    impl actix_web::FromRequest for CurrentUser {
+      type Error = crate::app::rest_api::ApiError;
+      type Future = std::future::Ready<Result<CurrentUser, Self::Error>>;
+
+      fn from_request(req: &HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+        std::future::ready(req.extensions().get::<CurrentUser>().cloned().ok_or(AuthError::Unauthenticated.into()))
+      }
+    }
+
+    pub fn session_cookie(token: &str) -> Cookie<'static> {
+      Cookie::build(SESSION_COOKIE, token.to_string())
+        .path("/")
+        .http_only(true)
+        .secure(*SECURE_COOKIES)
+        .same_site(SameSite::Lax)
+        .max_age(Duration::days(SESSION_DAYS))
+        .finish()
+    }
+
+    pub fn expired_session_cookie() -> Cookie<'static> {
+      let mut cookie = session_cookie("");
+      cookie.make_removal();
+      cookie
+    }
+
+    fn set_session_cookie(cookie: Cookie<'static>) {
+      if let (Some(response_options), Ok(cookie)) = (
+        use_context::<leptos_actix::ResponseOptions>(),
+        HeaderValue::from_str(&cookie.to_string()),
+      ) {
+        response_options.append_header(header::SET_COOKIE, cookie);
+      }
+    }
+
+    // what can be reached without logging in: the login page, its server fns, the sso redirects and static files
+    pub fn is_public_path(path: &str) -> bool {
+      path == LOGIN_PATH
+        || path == LoginSrv::PATH
+        || path == SsoEnabledSrv::PATH
+        || path == OIDC_LOGIN_PATH
+        || path == OIDC_CALLBACK_PATH
+        || path == "/favicon.ico"
+        || path.starts_with("/pkg/")
+        || path.starts_with("/assets/")
+    }
+
+    // wraps the whole app: pages send anonymous visitors to the login page, apis answer 401
+    pub async fn require_session(
+      req: ServiceRequest,
+      next: Next<impl actix_web::body::MessageBody>,
+    ) -> Result<ServiceResponse<EitherBody<impl actix_web::body::MessageBody>>, actix_web::Error> {
+      if is_public_path(req.path()) {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+      }
+
+      match current_user(req.request()).await {
+        Some(current_user) => {
+          req.extensions_mut().insert(current_user);
+          next.call(req).await.map(ServiceResponse::map_into_left_body)
+        }
+        None if req.path().starts_with("/api") => {
+          let auth_error = match bearer_token(req.request()) {
+            Some(_) => AuthError::InvalidToken,
+            None => AuthError::Unauthenticated,
+          };
+          let response = HttpResponse::Unauthorized().json(ApiErrorBody {
+            error: auth_error.to_string(),
+            fields: None,
+          });
+          Ok(req.into_response(response).map_into_right_body())
+        }
+        None => {
+          let response = HttpResponse::Found().insert_header((header::LOCATION, LOGIN_PATH)).finish();
+          Ok(req.into_response(response).map_into_right_body())
+        }
+      }
+    }
+  }
+}
+
+#[component]
+pub fn LoginPage() -> impl IntoView {
+  let error = use_query_map().with_untracked(|query| query.get("error").cloned());
+  let error_message = error.map(|error| match error.as_str() {
+    "sso" => "Single sign-on failed, please try again",
+    _ => "Invalid username or password",
+  });
+  let sso_enabled = create_resource(|| (), |_| async move { sso_enabled_srv().await });
+
+  // a plain post rather than an action form, the session cookie comes back with a full page load
+  view! {
+    <Title text="Log in | Dashboard App"/>
+    <body class="bg-gray-900 overflow-x-hidden">
+      <div class="flex flex-col w-full mx-auto items-center mt-28 text-white">
+        <form method="post" action=LoginSrv::PATH class=LOGIN_FORM_STYLE>
+          <CsrfField/>
+          <Show when=move || error_message.is_some()>
+            <p class="text-white bg-red-500 rounded w-full h-12 px-5 py-3">
+              {error_message}
+            </p>
+          </Show>
+          <p class="text-white pt-5">"Log in to the dashboard"</p>
+          <input type="text" name="username" placeholder="Username" autocomplete="username"
+            class=LOGIN_INPUT_STYLE required/>
+          <input type="password" name="password" placeholder="Password" autocomplete="current-password"
+            class=LOGIN_INPUT_STYLE required/>
+          <div class="flex flex-row w-full justify-end">
+            <button type="submit" class=LOGIN_BUTTON_STYLE>"Log in"</button>
+          </div>
+          // a server route rather than a page, so the router must not handle the link
+          <Transition fallback=|| ()>
+            <Show when=move || sso_enabled.get().and_then(Result::ok).unwrap_or(false)>
+              <a href=OIDC_LOGIN_PATH rel="external" class=SSO_LINK_STYLE>"Log in with your company account"</a>
+            </Show>
+          </Transition>
+        </form>
+      </div>
+    </body>
+  }
+}
+
+// the logged in user, provided by `App` for the role checks in the ui
+pub fn provide_current_user() {
+  let current_user: CurrentUserResource = create_resource(|| (), |_| async move { current_user_srv().await });
+  provide_context(current_user);
+}
+
+// none while loading or logged out, read it under a `Suspense`/`Transition` so the controls appear once known
+pub fn use_current_user() -> Signal<Option<CurrentUser>> {
+  let current_user = expect_context::<CurrentUserResource>();
+  Signal::derive(move || current_user.get().and_then(Result::ok).flatten())
+}
+
+// name of the logged in user and the logout button, part of the header
+#[component]
+pub fn UserMenu() -> impl IntoView {
+  let current_user = use_current_user();
+
+  view! {
+    <Transition fallback=|| ()>
+      {move || current_user.get().map(|current_user| view! {
+        <form method="post" action=LogoutSrv::PATH class="flex flex-row items-center ml-auto h-8">
+          <CsrfField/>
+          <span class="text-stone-400">{current_user.username}</span>
+          <button type="submit" class=LOGOUT_BUTTON_STYLE>"Log out"</button>
+        </form>
+      })}
+    </Transition>
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use super::{hash_password, is_public_path, verify_password, CurrentUser, LoginSrv, Role, LOGIN_PATH};
+  use crate::app::OIDC_CALLBACK_PATH;
+  use crate::app::{AuthError, EditPersonRequest, Person};
+  use leptos::server_fn::ServerFn;
+
+  #[test]
+  fn roles_decide_who_sees_and_edits_pay() {
+    let report = Person::new("r1".into(), "Ada".into(), "Engineer".into(), "Senior".into(), 9000, String::new())
+      .with_manager(Some("grace".into()));
+    let other = Person::new("o1".into(), "Linus".into(), "Designer".into(), "Junior".into(), 5000, String::new());
+    let user = |username: &str, role| CurrentUser { username: username.into(), role, token_scope: None };
+
+    let viewer = user("ada", Role::Viewer);
+    assert!(viewer.redact(report.clone()).is_compensation_redacted());
+    // clients get null, never an amount that could pass for pay
+    assert_eq!(serde_json::to_value(viewer.redact(report.clone())).unwrap()["compensation"], serde_json::Value::Null);
+    assert!(!viewer.can_add_and_delete());
+
+    let manager = user("grace", Role::Manager);
+    assert_eq!(manager.redact(report.clone()).compensation, Some(9000));
+    assert!(manager.redact(other.clone()).is_compensation_redacted());
+    // managers can change their reports but not hand them to someone else
+    let edit = EditPersonRequest::new("r1".into(), "Lead".into(), "Senior".into(), 9500).with_manager(None);
+    assert_eq!(manager.authorize_edit(&report, edit.clone()).unwrap().manager.as_deref(), Some("grace"));
+    assert!(matches!(manager.authorize_edit(&other, edit.clone()), Err(AuthError::Forbidden)));
+
+    let hr_admin = user("hr", Role::HrAdmin);
+    assert_eq!(hr_admin.redact(other).compensation, Some(5000));
+    assert_eq!(hr_admin.authorize_edit(&report, edit).unwrap().manager, None);
+    assert!(hr_admin.can_add_and_delete());
+  }
+
+  #[test]
+  fn passwords_are_hashed_and_verified() {
+    let password_hash = hash_password("correct horse").unwrap();
+    assert!(!password_hash.contains("correct horse"));
+    assert!(verify_password("correct horse", &password_hash));
+    assert!(!verify_password("wrong horse", &password_hash));
+    assert!(!verify_password("correct horse", "not a hash"));
+  }
+
+  #[test]
+  fn only_login_and_static_files_are_public() {
+    assert!(is_public_path(LOGIN_PATH));
+    assert!(is_public_path(LoginSrv::PATH));
+    assert!(is_public_path(OIDC_CALLBACK_PATH));
+    assert!(is_public_path("/pkg/dashboard-app.wasm"));
+    assert!(!is_public_path("/team"));
+    assert!(!is_public_path("/api/v1/persons"));
+    assert!(!is_public_path("/api/get_persons_srv"));
+  }
+}
@@ -0,0 +1,322 @@
+//changes applied to many selected members at once
+//the same plan is used for the preview in the browser, the optimistic list update and the server side save
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::app::compensation::format_amount;
+use crate::app::{on_action_success, use_current_user, use_person_store, BulkUpdateSrv, Person, Toast, ToastMessage, ToastMessageType};
+
+const BAR_STYLE: &str = "flex flex-col w-full max-w-[52rem] bg-[#222222] border-t-4 border-[#7734e7] rounded px-6 py-4 mt-6";
+
+const BAR_INPUT_STYLE: &str = "h-10 bg-[#333333] px-4 text-white mr-3 outline-none";
+
+const APPLY_BUTTON_STYLE: &str = "bg-[#7734e7] px-6 py-2 rounded text-white transition-all duration-1000 ease-in-out
+hover:bg-[#8448e9] disabled:bg-[#555555] disabled:cursor-not-allowed";
+
+// the most members one bulk change may select, each one is checked and saved in a single transaction
+pub const MAX_BULK_MEMBERS: usize = 200;
+
+const CLEAR_BUTTON_STYLE: &str = "bg-[#555555] px-6 py-2 mr-3 rounded text-white transition-all duration-1000 ease-in-out hover:bg-[#666666]";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BulkAction {
+  // empty or missing fields keep their current value
+  ChangeRole { title: Option<String>, level: Option<String> },
+  // e.g. 3.5 for a 3.5% raise or -10 for a 10% cut, rounded to a whole amount
+  AdjustCompensation { percent: f64 },
+  Delete,
+}
+
+impl BulkAction {
+  // the member after the change, none when it removes them
+  // a raise or cut that takes the pay past what an `i32` holds is an error rather than clamped
+  pub fn apply_to(&self, person: &Person) -> Result<Option<Person>, String> {
+    let mut changed_person = person.clone();
+    match self {
+      BulkAction::ChangeRole { title, level } => {
+        if let Some(title) = title.as_ref().filter(|title| !title.trim().is_empty()) {
+          changed_person.title = title.trim().to_string();
+        }
+        if let Some(level) = level.as_ref().filter(|level| !level.trim().is_empty()) {
+          changed_person.level = level.trim().to_string();
+        }
+      }
+      BulkAction::AdjustCompensation { percent } => {
+        if let Some(compensation) = person.compensation {
+          let adjusted = (f64::from(compensation) * (1.0 + percent / 100.0)).round();
+          if !(f64::from(i32::MIN)..=f64::from(i32::MAX)).contains(&adjusted) {
+            return Err(String::from("compensation is out of range"));
+          }
+          changed_person.compensation = Some(adjusted as i32);
+        }
+      }
+      BulkAction::Delete => return Ok(None),
+    }
+    Ok(Some(changed_person))
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BulkRequest {
+  pub uuids: Vec<String>,
+  pub action: BulkAction,
+}
+
+impl BulkRequest {
+  // why the selection can't be changed at all, before any member is looked at
+  pub fn selection_error(&self) -> Option<String> {
+    match self.uuids.len() {
+      0 => Some(String::from("no members selected")),
+      selected if selected > MAX_BULK_MEMBERS => {
+        Some(format!("at most {} members can be changed at once", MAX_BULK_MEMBERS))
+      }
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BulkResult {
+  pub updated: Vec<Person>,
+  pub deleted: Vec<String>,
+}
+
+impl BulkResult {
+  pub fn apply_to_list(&self, persons: &mut Vec<Person>) {
+    persons.retain(|person| !self.deleted.contains(&person.uuid));
+    for updated_person in &self.updated {
+      if let Some(person) = persons.iter_mut().find(|person| person.uuid == updated_person.uuid) {
+        *person = updated_person.clone();
+      }
+    }
+  }
+}
+
+// what a bulk request would do to the members it selects
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BulkPlan {
+  pub changes: BulkResult,
+  // selected uuids that are not in the list
+  pub missing: usize,
+  // "name: reason" for every member the change would leave invalid
+  pub invalid: Vec<String>,
+  pub cost_before: i64,
+  pub cost_after: i64,
+}
+
+impl BulkPlan {
+  pub fn new(persons: &[Person], bulk_request: &BulkRequest) -> BulkPlan {
+    let mut plan = BulkPlan::default();
+    if let Some(selection_error) = bulk_request.selection_error() {
+      plan.invalid.push(selection_error);
+      return plan;
+    }
+    for uuid in &bulk_request.uuids {
+      let Some(person) = persons.iter().find(|person| &person.uuid == uuid) else {
+        plan.missing += 1;
+        continue;
+      };
+      plan.cost_before += person.compensation.map_or(0, i64::from);
+
+      match bulk_request.action.apply_to(person) {
+        Ok(Some(changed_person)) => {
+          if let Some(message) = first_validation_message(&changed_person) {
+            plan.invalid.push(format!("{}: {}", person.name, message));
+          }
+          plan.cost_after += changed_person.compensation.map_or(0, i64::from);
+          plan.changes.updated.push(changed_person);
+        }
+        Ok(None) => plan.changes.deleted.push(person.uuid.clone()),
+        Err(message) => plan.invalid.push(format!("{}: {}", person.name, message)),
+      }
+    }
+    plan
+  }
+
+  pub fn affected(&self) -> usize {
+    self.changes.updated.len() + self.changes.deleted.len()
+  }
+
+  pub fn cost_change(&self) -> i64 {
+    self.cost_after - self.cost_before
+  }
+}
+
+fn first_validation_message(person: &Person) -> Option<String> {
+  let validation_errors = person.validate().err()?;
+  let field_errors = validation_errors.field_errors();
+  let (field, errors) = field_errors.iter().min_by_key(|(field, _)| **field)?;
+  let first_error = errors.first()?;
+  Some(match &first_error.message {
+    Some(message) => message.to_string(),
+    None => format!("{} is invalid", field),
+  })
+}
+
+// selecting members and a bulk change for them, shown above the team list while something is selected
+#[component]
+pub fn BulkActionsBar(
+  selected: RwSignal<Vec<String>>,
+  set_if_show_toast: WriteSignal<bool>,
+  set_toast_message: WriteSignal<ToastMessage>,
+) -> impl IntoView {
+  let person_store = use_person_store();
+  let bulk_action = person_store.bulk_action();
+  let current_user = use_current_user();
+  let can_delete = move || current_user.with(|current_user| {
+    current_user.as_ref().is_some_and(|current_user| current_user.can_add_and_delete())
+  });
+
+  let (kind, set_kind) = create_signal(String::from("role"));
+  let (title, set_title) = create_signal(String::new());
+  let (level, set_level) = create_signal(String::new());
+  let (percent, set_percent) = create_signal(String::new());
+
+  let bulk_request = create_memo(move |_| {
+    let action = match kind.get().as_str() {
+      "compensation" => BulkAction::AdjustCompensation { percent: percent.get().trim().parse().ok()? },
+      "delete" => BulkAction::Delete,
+      _ => {
+        if title.with(|title| title.trim().is_empty()) && level.with(|level| level.trim().is_empty()) {
+          return None;
+        }
+        BulkAction::ChangeRole { title: Some(title.get()), level: Some(level.get()) }
+      }
+    };
+    Some(BulkRequest { uuids: selected.get(), action })
+  });
+
+  let plan = create_memo(move |_| {
+    let bulk_request = bulk_request.get()?;
+    let persons = person_store.persons()?.ok()?;
+    Some(BulkPlan::new(&persons, &bulk_request))
+  });
+  let can_apply = move || {
+    !bulk_action.pending().get()
+      && plan.with(|plan| plan.as_ref().is_some_and(|plan| plan.invalid.is_empty() && plan.affected() > 0))
+  };
+
+  on_action_success(bulk_action, move |bulk_result| {
+    selected.set(Vec::new());
+    set_toast_message.set(ToastMessage::create(ToastMessageType::BulkApplied {
+      updated: bulk_result.updated.len(),
+      deleted: bulk_result.deleted.len(),
+    }));
+    set_if_show_toast.set(true);
+  });
+  on_cleanup(move || {
+    bulk_action.value().try_set(None);
+  });
+
+  let error_message = move || {
+    bulk_action.value().with(|value| match value {
+      Some(Err(ServerFnError::Args(message))) | Some(Err(ServerFnError::Response(message))) => Some(message.clone()),
+      Some(Err(_)) => Some(String::from("Error applying the change, Please try again later")),
+      _ => None,
+    })
+  };
+
+  let on_apply = move |_| {
+    if let Some(bulk_request) = bulk_request.get_untracked() {
+      bulk_action.dispatch(BulkUpdateSrv { bulk_request });
+    }
+  };
+
+  view! {
+    <div class=BAR_STYLE>
+      <Show when=move || error_message().is_some()>
+        <p class="text-white bg-red-500 rounded w-full px-5 py-3 mb-3">{error_message}</p>
+      </Show>
+      <div class="flex flex-row items-center">
+        <p class="text-white mr-4">{move || format!("{} selected", selected.with(Vec::len))}</p>
+        <select class=BAR_INPUT_STYLE on:change=move |event| set_kind.set(event_target_value(&event))>
+          <option value="role" selected=move || kind.get() == "role">"Change title/level"</option>
+          <option value="compensation" selected=move || kind.get() == "compensation">"Adjust compensation"</option>
+          <Show when=can_delete>
+            <option value="delete" selected=move || kind.get() == "delete">"Delete"</option>
+          </Show>
+        </select>
+        <Show when=move || kind.get() == "role">
+          <input type="text" placeholder="New title" class=BAR_INPUT_STYLE prop:value=title
+            on:input=move |event| set_title.set(event_target_value(&event))/>
+          <input type="text" placeholder="New level" class=BAR_INPUT_STYLE prop:value=level
+            on:input=move |event| set_level.set(event_target_value(&event))/>
+        </Show>
+        <Show when=move || kind.get() == "compensation">
+          <input type="number" step="0.1" placeholder="Change in %" class=BAR_INPUT_STYLE prop:value=percent
+            on:input=move |event| set_percent.set(event_target_value(&event))/>
+        </Show>
+      </div>
+      <div class="flex flex-row items-center justify-between mt-4">
+        <div class="text-sm">
+          {move || plan.get().map(|plan| view! {
+            <p class="text-stone-400">
+              {format!(
+                "Total cost of the {} members: {} → {} ({}{})",
+                plan.affected(),
+                format_amount(plan.cost_before),
+                format_amount(plan.cost_after),
+                if plan.cost_change() > 0 { "+" } else { "" },
+                format_amount(plan.cost_change()),
+              )}
+            </p>
+            {plan.invalid.into_iter().map(|message| view! { <p class="text-red-400">{message}</p> }).collect_view()}
+          })}
+        </div>
+        <div class="flex flex-row">
+          <button type="button" class=CLEAR_BUTTON_STYLE on:click=move |_| selected.set(Vec::new())>"Clear"</button>
+          <button type="button" class=APPLY_BUTTON_STYLE disabled=move || !can_apply() on:click=on_apply>
+            {move || format!("Apply to {} Members", selected.with(Vec::len))}
+          </button>
+        </div>
+      </div>
+    </div>
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use super::{BulkAction, BulkPlan, BulkRequest, MAX_BULK_MEMBERS};
+  use crate::app::Person;
+
+  #[test]
+  fn plan_previews_cost_change_and_flags_invalid_members() {
+    let persons = vec![
+      Person::test_member("A", "Engineer", "Senior", 10000),
+      Person::test_member("B", "Engineer", "Senior", 2000),
+      Person::test_member("C", "Engineer", "Senior", 5000),
+    ];
+
+    let raise = BulkRequest { uuids: vec!["a".into(), "c".into()], action: BulkAction::AdjustCompensation { percent: 2.5 } };
+    let plan = BulkPlan::new(&persons, &raise);
+    assert_eq!((plan.cost_before, plan.cost_after, plan.cost_change()), (15000, 15375, 375));
+    assert!(plan.invalid.is_empty());
+
+    let cut = BulkRequest { uuids: vec!["b".into(), "gone".into()], action: BulkAction::AdjustCompensation { percent: -10.0 } };
+    let plan = BulkPlan::new(&persons, &cut);
+    assert_eq!(plan.missing, 1);
+    assert_eq!(plan.invalid, vec!["B: compensation must be between 2000 and 99999"]);
+
+    let delete = BulkRequest { uuids: vec!["a".into(), "b".into()], action: BulkAction::Delete };
+    let plan = BulkPlan::new(&persons, &delete);
+    assert_eq!(plan.cost_change(), -12000);
+
+    let mut remaining = persons.clone();
+    plan.changes.apply_to_list(&mut remaining);
+    assert_eq!(remaining, vec![Person::test_member("C", "Engineer", "Senior", 5000)]);
+  }
+
+  #[test]
+  fn pay_past_i32_and_oversized_selections_are_flagged() {
+    let persons = vec![Person::test_member("A", "Engineer", "Senior", 10000)];
+    let huge_raise = BulkRequest { uuids: vec!["a".into()], action: BulkAction::AdjustCompensation { percent: 1e9 } };
+    let plan = BulkPlan::new(&persons, &huge_raise);
+    assert_eq!(plan.invalid, vec!["A: compensation is out of range"]);
+    assert_eq!(plan.affected(), 0);
+
+    let everyone = BulkRequest { uuids: vec![String::from("a"); MAX_BULK_MEMBERS + 1], action: BulkAction::Delete };
+    assert_eq!(BulkPlan::new(&persons, &everyone).invalid, vec![format!("at most {} members can be changed at once", MAX_BULK_MEMBERS)]);
+    assert!(BulkRequest { uuids: Vec::new(), action: BulkAction::Delete }.selection_error().is_some());
+  }
+}
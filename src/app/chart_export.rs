@@ -0,0 +1,257 @@
+//downloads of the dashboard charts as svg or png, for slide decks
+//the server draws them again from the same figures and theme as the dashboard, for the period in the url
+use leptos::*;
+
+use crate::app::{use_period, Period};
+
+pub const CHART_EXPORT_PATH: &str = "/api/charts";
+
+const DOWNLOADS_STYLE: &str = "flex flex-row justify-end gap-4 mt-2 text-sm";
+const DOWNLOAD_LINK_STYLE: &str = "text-stone-400 hover:text-white";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartFormat {
+  Svg,
+  Png,
+}
+
+impl ChartFormat {
+  pub const ALL: [ChartFormat; 2] = [ChartFormat::Svg, ChartFormat::Png];
+
+  // value of the `format` query param and the file extension
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ChartFormat::Svg => "svg",
+      ChartFormat::Png => "png",
+    }
+  }
+
+  pub fn from_param(format: &str) -> Option<ChartFormat> {
+    ChartFormat::ALL.into_iter().find(|chart_format| chart_format.as_str() == format)
+  }
+
+  pub fn content_type(&self) -> &'static str {
+    match self {
+      ChartFormat::Svg => "image/svg+xml",
+      ChartFormat::Png => "image/png",
+    }
+  }
+}
+
+// every chart of the dashboard, in the order they're shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DashboardChartKind {
+  Headcount,
+  History,
+  TotalCompensation,
+  AverageCompensation,
+  CompensationDistribution,
+  LevelHeatmap,
+}
+
+impl DashboardChartKind {
+  pub const ALL: [DashboardChartKind; 6] = [
+    DashboardChartKind::Headcount,
+    DashboardChartKind::History,
+    DashboardChartKind::TotalCompensation,
+    DashboardChartKind::AverageCompensation,
+    DashboardChartKind::CompensationDistribution,
+    DashboardChartKind::LevelHeatmap,
+  ];
+
+  // last part of the export path and start of the file name
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      DashboardChartKind::Headcount => "headcount",
+      DashboardChartKind::History => "history",
+      DashboardChartKind::TotalCompensation => "compensation-total",
+      DashboardChartKind::AverageCompensation => "compensation-average",
+      DashboardChartKind::CompensationDistribution => "compensation-distribution",
+      DashboardChartKind::LevelHeatmap => "levels",
+    }
+  }
+
+  pub fn from_param(chart: &str) -> Option<DashboardChartKind> {
+    DashboardChartKind::ALL.into_iter().find(|kind| kind.as_str() == chart)
+  }
+
+  pub fn title(&self) -> &'static str {
+    match self {
+      DashboardChartKind::Headcount => "Members per Title",
+      DashboardChartKind::History => "Headcount and Cost over Time",
+      DashboardChartKind::TotalCompensation => "Total Compensation per Title",
+      DashboardChartKind::AverageCompensation => "Average Compensation per Title",
+      DashboardChartKind::CompensationDistribution => "Compensation Distribution",
+      DashboardChartKind::LevelHeatmap => "Members by Level and Title",
+    }
+  }
+}
+
+// e.g. `/api/charts/headcount?period=quarter&from=2024-07&format=png`
+// `range` is the number of months of the history chart
+pub fn chart_export_href(kind: DashboardChartKind, format: ChartFormat, period: &Period, range: Option<u32>) -> String {
+  let mut href = format!("{}/{}{}&format={}", CHART_EXPORT_PATH, kind.as_str(), period.query(), format.as_str());
+  if let Some(range) = range {
+    href.push_str(&format!("&range={}", range));
+  }
+  href
+}
+
+// e.g. headcount-2024-07.png, named after the first month of the period
+pub fn chart_filename(kind: DashboardChartKind, format: ChartFormat, period: &Period) -> String {
+  format!("{}-{}.{}", kind.as_str(), period.from.format("%Y-%m"), format.as_str())
+}
+
+// svg and png links under a chart, for the period of the page
+#[component]
+pub fn ChartDownloads(
+  chart: DashboardChartKind,
+  // months of the history chart
+  #[prop(default = None)] range: Option<RwSignal<u32>>,
+) -> impl IntoView {
+  let period = use_period();
+
+  view! {
+    <div class=DOWNLOADS_STYLE>
+      {ChartFormat::ALL.into_iter().map(|format| view! {
+        // `download` keeps the router from treating the link as a page
+        <a class=DOWNLOAD_LINK_STYLE rel="external"
+          href=move || chart_export_href(chart, format, &period.get(), range.map(|range| range.get()))
+          download=move || chart_filename(chart, format, &period.get())
+        >
+          {format!("Download {}", format.as_str().to_uppercase())}
+        </a>
+      }).collect_view()}
+    </div>
+  }
+}
+
+cfg_if::cfg_if! {
+  if #[cfg(feature = "ssr")] {
+    use actix_web::http::header;
+    use actix_web::{get, web, HttpResponse};
+    use chrono::Local;
+    use leptos_router::ParamsMap;
+
+    use crate::app::compensation::compensation_charts;
+    use crate::app::dashboard::CHART_FONT;
+    use crate::app::history::{history_chart_svg, last_months, members_on, parse_month, MAX_HISTORY_MONTHS};
+    use crate::app::rest_api::ApiError;
+    use crate::app::serv_functions::retrieve_redacted_team;
+    use crate::app::{
+      get_dashboard_aggregates, headcount_chart, CompensationStats, CurrentUser, DashboardStats, RenderError, TeamHistory,
+      DEFAULT_HISTORY_RANGE,
+    };
+
+    // the chart as the dashboard shows it to `current_user` for `period`
+    pub async fn render_chart_svg(
+      kind: DashboardChartKind,
+      current_user: &CurrentUser,
+      period: &Period,
+      range: u32,
+    ) -> Result<String, ApiError> {
+      let svg = match kind {
+        DashboardChartKind::Headcount => {
+          let aggregates = get_dashboard_aggregates(current_user.username.clone(), period.as_of()).await?;
+          headcount_chart(&DashboardStats::new(aggregates, current_user)).svg().ok()
+        }
+        DashboardChartKind::History => {
+          let (persons, departures) = retrieve_redacted_team(current_user).await;
+          let (from, to) = last_months(range, period.to);
+          let (Some(from), Some(to)) = (parse_month(&from), parse_month(&to)) else {
+            return Err(RenderError::RenderFailure.into());
+          };
+          history_chart_svg(&TeamHistory::new(&persons, &departures, from, to))
+        }
+        _ => {
+          let (persons, departures) = retrieve_redacted_team(current_user).await;
+          let stats = CompensationStats::new(&members_on(&persons, &departures, period.as_of()));
+          compensation_charts(&stats)
+            .into_iter()
+            .find(|(chart_kind, _)| *chart_kind == kind)
+            .map(|(_, svg)| svg)
+        }
+      };
+      svg.ok_or(RenderError::NoData.into())
+    }
+
+    // the server only has the font charts-rs bundles, so png text falls back to it
+    pub fn svg_to_png(svg: &str) -> Result<Vec<u8>, RenderError> {
+      let svg = svg.replace(&format!("font-family=\"{}\"", CHART_FONT), &format!("font-family=\"{}, Roboto\"", CHART_FONT));
+      charts_rs::svg_to_png(&svg).map_err(|e| {
+        println!("Error in rendering a chart: {:?}", e);
+        RenderError::RenderFailure
+      })
+    }
+
+    // `/api/charts/{chart}?format=svg|png&range=..` and the period params of the dashboard url
+    #[get("/api/charts/{chart}")]
+    pub async fn export_chart(
+      current_user: CurrentUser,
+      chart: web::Path<String>,
+      query: web::Query<Vec<(String, String)>>,
+    ) -> Result<HttpResponse, ApiError> {
+      let chart = chart.into_inner();
+      let kind = DashboardChartKind::from_param(&chart).ok_or(RenderError::UnknownChart(chart))?;
+      let mut format = ChartFormat::Svg;
+      let mut range = DEFAULT_HISTORY_RANGE;
+      let mut period_params = ParamsMap::new();
+      for (key, value) in query.into_inner() {
+        match key.as_str() {
+          "format" => {
+            format = ChartFormat::from_param(&value)
+              .ok_or_else(|| ApiError::InvalidQuery(format!("unknown format \"{}\"", value)))?;
+          }
+          "range" => {
+            range = value.parse().ok()
+              .filter(|months| (1..=MAX_HISTORY_MONTHS as u32).contains(months))
+              .ok_or_else(|| ApiError::InvalidQuery(format!("range must be 1 to {} months", MAX_HISTORY_MONTHS)))?;
+          }
+          _ => {
+            period_params.insert(key, value);
+          }
+        }
+      }
+      let period = Period::from_query(&period_params, Local::now().date_naive());
+
+      let svg = render_chart_svg(kind, &current_user, &period, range).await?;
+      let body = match format {
+        ChartFormat::Svg => svg.into_bytes(),
+        ChartFormat::Png => svg_to_png(&svg)?,
+      };
+      let filename = chart_filename(kind, format, &period);
+      Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)))
+        .body(body))
+    }
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use chrono::NaiveDate;
+
+  use super::{chart_export_href, chart_filename, svg_to_png, ChartFormat, DashboardChartKind};
+  use crate::app::dashboard::{headcount_chart, DashboardStats, TitleCount};
+  use crate::app::{Period, PeriodKind};
+
+  #[test]
+  fn charts_export_as_svg_and_png() {
+    let period = Period::containing(PeriodKind::Quarter, NaiveDate::from_ymd_opt(2024, 8, 12).unwrap());
+    assert_eq!(
+      chart_export_href(DashboardChartKind::History, ChartFormat::Png, &period, Some(6)),
+      "/api/charts/history?period=quarter&from=2024-07&format=png&range=6",
+    );
+    assert_eq!(chart_filename(DashboardChartKind::Headcount, ChartFormat::Svg, &period), "headcount-2024-07.svg");
+    assert_eq!(DashboardChartKind::from_param("levels"), Some(DashboardChartKind::LevelHeatmap));
+
+    let stats = DashboardStats {
+      members: 2,
+      titles: vec![TitleCount { title: String::from("Engineer"), members: 2, names: Vec::new() }],
+      ..DashboardStats::default()
+    };
+    let png = svg_to_png(&headcount_chart(&stats).svg().unwrap()).unwrap();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+  }
+}
@@ -0,0 +1,170 @@
+//dashboard charts drawn by leptos as svg, with tooltips on hover and bars that link to the matching members
+//the svg is rendered on the server too, so the bar links work before the wasm has loaded and without it
+use leptos::*;
+use leptos_router::*;
+
+use crate::app::TEAM_PATH;
+
+// query parameter of the team page that limits the list to one title
+pub const TITLE_FILTER: &str = "title";
+
+// the same size as the charts-rs svg of the exported charts
+pub const CHART_WIDTH: f64 = 832.0;
+pub const CHART_HEIGHT: f64 = 500.0;
+const LABEL_HEIGHT: f64 = 40.0;
+const VALUE_HEIGHT: f64 = 30.0;
+
+// series color of the charts-rs dark theme, lighter while hovered
+const BAR_COLOR: &str = "#5470c6";
+const BAR_HOVER_COLOR: &str = "#7d95e0";
+
+const CHART_STYLE: &str = "relative w-full max-w-[41rem] bg-black-200 rounded py-10 px-4 pb-10";
+
+const TOOLTIP_STYLE: &str = "absolute top-4 -translate-x-1/2 bg-[#222222] border-t-4 border-[#7734e7] rounded
+px-4 py-2 text-sm text-white pointer-events-none whitespace-nowrap";
+
+// tooltips list this many names, the rest as a count
+pub const TOOLTIP_NAMES: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartBar {
+  pub label: String,
+  pub value: f64,
+  // shown above the bar, e.g. the count or a formatted amount
+  pub value_label: String,
+  // lines of the tooltip under the label
+  pub details: Vec<String>,
+  // where the bar links to
+  pub href: Option<String>,
+}
+
+pub fn team_filtered_by_title(title: &str) -> String {
+  format!("{}?{}={}", TEAM_PATH, TITLE_FILTER, escape(title))
+}
+
+// the names for a tooltip, cut off after a few, out of `members` in all
+pub fn tooltip_names(names: &[String], members: usize) -> Vec<String> {
+  let mut lines: Vec<String> = names.iter().take(TOOLTIP_NAMES).cloned().collect();
+  if members > lines.len() {
+    lines.push(format!("+{} more", members - lines.len()));
+  }
+  lines
+}
+
+// x, width and height of a bar, bars share the width evenly and the tallest fills the plot
+pub fn bar_geometry(index: usize, bar_count: usize, value: f64, max_value: f64) -> (f64, f64, f64) {
+  let slot = CHART_WIDTH / bar_count.max(1) as f64;
+  let width = slot * 0.6;
+  let plot_height = CHART_HEIGHT - LABEL_HEIGHT - VALUE_HEIGHT;
+  let height = if max_value > 0.0 { plot_height * value.max(0.0) / max_value } else { 0.0 };
+  (slot * index as f64 + (slot - width) / 2.0, width, height)
+}
+
+// what a screen reader announces for a bar, e.g. "Engineer: 2"
+pub fn bar_description(bar: &ChartBar) -> String {
+  format!("{}: {}", bar.label, bar.value_label)
+}
+
+// each bar is an svg link, so it can be tabbed to and opened with the keyboard
+// the tooltip shows for the hovered or focused bar
+#[component]
+pub fn InteractiveBarChart(bars: Vec<ChartBar>, #[prop(into)] label: String) -> impl IntoView {
+  let (hovered, set_hovered) = create_signal(None::<usize>);
+  let navigate = use_navigate();
+  let max_value = bars.iter().map(|bar| bar.value).fold(0.0, f64::max);
+  let bars = store_value(bars);
+
+  let tooltip = move || {
+    let index = hovered.get()?;
+    let bar = bars.with_value(|bars| bars.get(index).cloned())?;
+    let (x, width, _) = bar_geometry(index, bars.with_value(Vec::len), bar.value, max_value);
+    let left = (x + width / 2.0) / CHART_WIDTH * 100.0;
+    Some(view! {
+      <div class=TOOLTIP_STYLE style=format!("left: {:.1}%", left)>
+        <p class="font-bold">{bar.label}</p>
+        {bar.details.into_iter().map(|line| view! { <p class="text-stone-400">{line}</p> }).collect_view()}
+      </div>
+    })
+  };
+
+  view! {
+    <div class=CHART_STYLE>
+      <svg viewBox=format!("0 0 {} {}", CHART_WIDTH, CHART_HEIGHT) width="100%" aria-label=label>
+        {bars.get_value().into_iter().enumerate().map(|(index, bar)| {
+          let (x, width, height) = bar_geometry(index, bars.with_value(Vec::len), bar.value, max_value);
+          let y = CHART_HEIGHT - LABEL_HEIGHT - height;
+          let navigate = navigate.clone();
+          let description = bar_description(&bar);
+          let href = bar.href.clone();
+          view! {
+            // the g keeps the link an svg element, an a on its own is taken for an html one
+            <g>
+              // the router only picks up html links, so once hydrated the click is routed here
+              // a click with a modifier key is left to the browser, e.g. to open a new tab
+              <a href=bar.href aria-label=description class="outline-none focus:opacity-80"
+                on:mouseenter=move |_| set_hovered.set(Some(index))
+                on:mouseleave=move |_| set_hovered.set(None)
+                on:focus=move |_| set_hovered.set(Some(index))
+                on:blur=move |_| set_hovered.set(None)
+                on:click=move |event: ev::MouseEvent| {
+                  if event.ctrl_key() || event.meta_key() || event.shift_key() {
+                    return;
+                  }
+                  if let Some(href) = &href {
+                    event.prevent_default();
+                    navigate(href, Default::default());
+                  }
+                }
+              >
+                <rect x=x y=y width=width height=height rx="2"
+                  fill=move || if hovered.get() == Some(index) { BAR_HOVER_COLOR } else { BAR_COLOR }/>
+                <text x=x + width / 2.0 y=y - 8.0 text-anchor="middle" fill="#ffffff" font-size="14">
+                  {bar.value_label}
+                </text>
+                <text x=x + width / 2.0 y=CHART_HEIGHT - LABEL_HEIGHT + 24.0 text-anchor="middle" fill="#a8a29e" font-size="14">
+                  {bar.label}
+                </text>
+              </a>
+            </g>
+          }
+        }).collect_view()}
+      </svg>
+      {tooltip}
+    </div>
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use super::{bar_description, bar_geometry, team_filtered_by_title, tooltip_names, ChartBar, CHART_WIDTH};
+
+  #[test]
+  fn bars_share_the_width_and_scale_to_the_largest() {
+    let (first_x, width, tallest) = bar_geometry(0, 4, 10.0, 10.0);
+    let (last_x, _, half) = bar_geometry(3, 4, 5.0, 10.0);
+    assert_eq!(width, CHART_WIDTH / 4.0 * 0.6);
+    assert!(last_x + width < CHART_WIDTH && first_x > 0.0);
+    assert_eq!(half * 2.0, tallest);
+    assert_eq!(bar_geometry(0, 1, 3.0, 0.0).2, 0.0);
+  }
+
+  #[test]
+  fn links_and_tooltips_for_a_title() {
+    assert_eq!(team_filtered_by_title("QA & Ops"), "/team?title=QA%20%26%20Ops");
+    let bar = ChartBar {
+      label: "QA & Ops".to_string(),
+      value: 2.0,
+      value_label: "2".to_string(),
+      details: vec!["Ada".to_string(), "Grace".to_string()],
+      href: Some(team_filtered_by_title("QA & Ops")),
+    };
+    assert_eq!(bar_description(&bar), "QA & Ops: 2");
+
+    let names: Vec<String> = (1..=7).map(|number| format!("Member {}", number)).collect();
+    let lines = tooltip_names(&names, 7);
+    assert_eq!(lines.len(), 6);
+    assert_eq!(lines.last().unwrap(), "+2 more");
+    // the database only sends the names a tooltip shows
+    assert_eq!(tooltip_names(&names[..5], 40).last().unwrap(), "+35 more");
+  }
+}
@@ -0,0 +1,242 @@
+//compensation breakdown for finance: pay per title, how pay is spread and how levels spread over titles
+//computed on the server from the pay the caller may see, the charts only get the totals
+use charts_rs::{BarChart, HeatmapChart, Series, THEME_DARK};
+use leptos::*;
+use num_format::{Buffer, Locale};
+use serde::{Deserialize, Serialize};
+
+use crate::app::chart_export::{ChartDownloads, DashboardChartKind};
+use crate::app::dashboard::style_chart;
+use crate::app::Person;
+
+// width of a distribution bucket
+pub const BUCKET_SIZE: i32 = 10_000;
+
+const CHART_STYLE: &str = "w-full max-w-[41rem] bg-black-200 rounded py-10 px-4 pb-10";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TitleCompensation {
+  pub title: String,
+  pub members: usize,
+  pub total: i64,
+  pub average: i64,
+}
+
+// members paid from `from` up to but not including `to`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompensationBucket {
+  pub from: i32,
+  pub to: i32,
+  pub members: usize,
+}
+
+// headcount for every level and title, `counts[level][title]`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LevelHeatmap {
+  pub titles: Vec<String>,
+  pub levels: Vec<String>,
+  pub counts: Vec<Vec<usize>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompensationStats {
+  // titles by total pay, highest first
+  pub by_title: Vec<TitleCompensation>,
+  pub distribution: Vec<CompensationBucket>,
+  pub level_by_title: LevelHeatmap,
+  // members whose pay is hidden from the caller, left out of the pay figures
+  pub restricted: usize,
+}
+
+impl CompensationStats {
+  // expects pay the caller may not see to be redacted already
+  pub fn new(persons: &[Person]) -> CompensationStats {
+    let mut stats = CompensationStats::default();
+    let paid: Vec<(&Person, i32)> = persons
+      .iter()
+      .filter_map(|person| person.compensation.map(|compensation| (person, compensation)))
+      .collect();
+    stats.restricted = persons.len() - paid.len();
+
+    for (person, compensation) in &paid {
+      let compensation = i64::from(*compensation);
+      match stats.by_title.iter_mut().find(|title| title.title == person.title) {
+        Some(title) => {
+          title.members += 1;
+          title.total += compensation;
+        }
+        None => stats.by_title.push(TitleCompensation {
+          title: person.title.clone(),
+          members: 1,
+          total: compensation,
+          average: 0,
+        }),
+      }
+    }
+    for title in &mut stats.by_title {
+      title.average = title.total / title.members as i64;
+    }
+    stats.by_title.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.title.cmp(&b.title)));
+
+    // every bucket from the lowest to the highest pay, empty ones included so gaps show
+    let lowest = paid.iter().map(|(_, compensation)| compensation.div_euclid(BUCKET_SIZE)).min();
+    let highest = paid.iter().map(|(_, compensation)| compensation.div_euclid(BUCKET_SIZE)).max();
+    if let (Some(lowest), Some(highest)) = (lowest, highest) {
+      stats.distribution = (lowest..=highest)
+        .map(|bucket| CompensationBucket {
+          from: bucket * BUCKET_SIZE,
+          to: (bucket + 1) * BUCKET_SIZE,
+          members: paid.iter().filter(|(_, compensation)| compensation.div_euclid(BUCKET_SIZE) == bucket).count(),
+        })
+        .collect();
+    }
+
+    // titles and levels are visible to everyone, so the heatmap counts all members
+    let heatmap = &mut stats.level_by_title;
+    for person in persons {
+      if !heatmap.titles.contains(&person.title) {
+        heatmap.titles.push(person.title.clone());
+      }
+      if !heatmap.levels.contains(&person.level) {
+        heatmap.levels.push(person.level.clone());
+      }
+    }
+    heatmap.titles.sort();
+    heatmap.levels.sort();
+    heatmap.counts = heatmap.levels.iter().map(|level| {
+      heatmap.titles.iter().map(|title| {
+        persons.iter().filter(|person| &person.level == level && &person.title == title).count()
+      }).collect()
+    }).collect();
+    stats
+  }
+}
+
+// e.g. "$52,000" or "-$375"
+pub fn format_amount(amount: i64) -> String {
+  let mut buf = Buffer::default();
+  buf.write_formatted(&amount.unsigned_abs(), &Locale::en);
+  let sign = if amount < 0 { "-" } else { "" };
+  format!("{}${}", sign, buf.as_str())
+}
+
+fn bucket_label(bucket: &CompensationBucket) -> String {
+  format!("${}k-{}k", bucket.from / 1000, bucket.to / 1000)
+}
+
+fn bar_chart(title: &str, labels: Vec<String>, values: Vec<f32>) -> BarChart {
+  let mut series = Series::new(String::new(), values);
+  series.label_show = true;
+  let mut chart = BarChart::new_with_theme(vec![series], labels, THEME_DARK);
+  chart.title_text = title.to_string();
+  style_chart(&mut chart);
+  chart.y_axis_hidden = true;
+  chart
+}
+
+// kind and svg of every breakdown chart with data, in the order they're shown
+pub fn compensation_charts(stats: &CompensationStats) -> Vec<(DashboardChartKind, String)> {
+  let mut charts = Vec::new();
+  if !stats.by_title.is_empty() {
+    let titles: Vec<String> = stats.by_title.iter().map(|title| title.title.clone()).collect();
+    let totals = stats.by_title.iter().map(|title| title.total as f32).collect();
+    let averages = stats.by_title.iter().map(|title| title.average as f32).collect();
+    let total = DashboardChartKind::TotalCompensation;
+    let average = DashboardChartKind::AverageCompensation;
+    charts.push((total, bar_chart(total.title(), titles.clone(), totals).svg()));
+    charts.push((average, bar_chart(average.title(), titles, averages).svg()));
+  }
+  if !stats.distribution.is_empty() {
+    let labels = stats.distribution.iter().map(bucket_label).collect();
+    let members = stats.distribution.iter().map(|bucket| bucket.members as f32).collect();
+    let distribution = DashboardChartKind::CompensationDistribution;
+    charts.push((distribution, bar_chart(distribution.title(), labels, members).svg()));
+  }
+  let heatmap = &stats.level_by_title;
+  if !heatmap.titles.is_empty() {
+    // charts-rs finds the cell of title x and level y at `y * levels + x`, which only lines up
+    // when there are as many levels as titles, so the shorter axis is padded with blank labels
+    let size = heatmap.titles.len().max(heatmap.levels.len());
+    let pad = |labels: &[String]| {
+      let mut padded = labels.to_vec();
+      padded.resize(size, String::new());
+      padded
+    };
+    let cells = heatmap.counts.iter().enumerate().flat_map(|(level_index, counts)| {
+      counts.iter().enumerate()
+        .filter(|(_, count)| **count > 0)
+        .map(move |(title_index, count)| (level_index * size + title_index, *count as f32))
+    }).collect();
+    let mut chart = HeatmapChart::new_with_theme(cells, pad(&heatmap.titles), pad(&heatmap.levels), THEME_DARK);
+    chart.title_text = DashboardChartKind::LevelHeatmap.title().to_string();
+    style_chart(&mut chart);
+    charts.push((DashboardChartKind::LevelHeatmap, chart.svg()));
+  }
+  charts
+    .into_iter()
+    .filter_map(|(kind, svg)| svg.ok().map(|svg| (kind, svg)))
+    .collect()
+}
+
+// the breakdown below the headcount chart on the dashboard
+#[component]
+pub fn CompensationCharts(stats: CompensationStats) -> impl IntoView {
+  let restricted = stats.restricted;
+  let charts = compensation_charts(&stats);
+  let paid_members = stats.by_title.iter().map(|title| title.members as i64).sum::<i64>();
+  let average_pay = (paid_members > 0)
+    .then(|| stats.by_title.iter().map(|title| title.total).sum::<i64>() / paid_members);
+
+  view! {
+    <div class="max-w-[53rem] mx-auto w-full flex flex-col pb-12">
+      <div class="flex flex-row items-end mb-6">
+        <div class="pr-4 text-xl">"Compensation"</div>
+        <div class="text-stone-400">{average_pay.map(|average_pay| format!("average {}", format_amount(average_pay)))}</div>
+      </div>
+      <Show when=move || restricted != 0>
+        <p class="text-stone-400 mb-6">
+          {format!("Pay of {} members is restricted and not included", restricted)}
+        </p>
+      </Show>
+      {charts.into_iter().map(|(kind, svg)| view! {
+        <div class="mb-6">
+          <div class=CHART_STYLE inner_html=svg></div>
+          <ChartDownloads chart=kind/>
+        </div>
+      }).collect_view()}
+    </div>
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use super::{compensation_charts, format_amount, CompensationBucket, CompensationStats};
+  use crate::app::Person;
+
+  #[test]
+  fn stats_total_average_spread_and_count_levels() {
+    let persons = vec![
+      Person::test_member("Ann", "Engineer", "Senior", 52000),
+      Person::test_member("Bob", "Engineer", "Junior", 31000),
+      Person::test_member("Cy", "Designer", "Senior", 45000),
+      Person { compensation: None, ..Person::test_member("Di", "Designer", "Senior", 47000) },
+    ];
+    let stats = CompensationStats::new(&persons);
+
+    let by_title: Vec<(&str, usize, i64, i64)> = stats.by_title.iter()
+      .map(|title| (title.title.as_str(), title.members, title.total, title.average))
+      .collect();
+    assert_eq!(by_title, vec![("Engineer", 2, 83000, 41500), ("Designer", 1, 45000, 45000)]);
+    assert_eq!(stats.restricted, 1);
+    assert_eq!(stats.distribution.len(), 3);
+    assert_eq!(stats.distribution[1], CompensationBucket { from: 40000, to: 50000, members: 1 });
+
+    assert_eq!(stats.level_by_title.titles, vec!["Designer", "Engineer"]);
+    assert_eq!(stats.level_by_title.levels, vec!["Junior", "Senior"]);
+    assert_eq!(stats.level_by_title.counts, vec![vec![0, 1], vec![2, 1]]);
+
+    assert_eq!((format_amount(52000), format_amount(-375)), (String::from("$52,000"), String::from("-$375")));
+    assert_eq!(compensation_charts(&stats).len(), 4);
+    assert!(compensation_charts(&CompensationStats::default()).is_empty());
+  }
+}
@@ -3,7 +3,7 @@ use leptos::*;
 use chrono::{ DateTime, Datelike, Local, Month};
 use leptos_router::*;
 use validator::Validate;
-use crate::app::{AddPersonRequest, Person};
+use crate::app::{AddPersonRequest, Person, Period, UserMenu, use_current_user, API_TOKENS_PATH};
 
 
 const INPUT_STYLE: &str = "border-b-0 border-[#7734e7] h-8 text-white ml-4 mr-4 hover:border-b-2";
@@ -27,6 +27,12 @@ pub fn Header() -> impl IntoView {
     }
   });
 
+  // only hr admins hand out api tokens
+  let current_user = use_current_user();
+  let can_manage_api_tokens = move || current_user.with(|current_user| {
+    current_user.as_ref().is_some_and(|current_user| current_user.can_manage_api_tokens())
+  });
+
   view! {
     <div class="flex mx-auto align-middle items-center w-full h-12 pt-8 px-20 top-0 fixed">
       <nav class="flex flex-row w-full max-w-[52rem] h-12">
@@ -36,28 +42,35 @@ pub fn Header() -> impl IntoView {
         <div class={move || get_style_from_url(&current_path, "/team")}>
           <A href="/team">"Team"</A>
         </div>
+        <Transition fallback=|| ()>
+          <Show when=can_manage_api_tokens>
+            <div class={move || get_style_from_url(&current_path, API_TOKENS_PATH)}>
+              <A href=API_TOKENS_PATH>"API Tokens"</A>
+            </div>
+          </Show>
+        </Transition>
+        <UserMenu/>
       </nav>
     </div>
   }
 }
 
 fn get_style_from_url<'a, 'b>(url: &'a ReadSignal<String>, match_url: &'a str) -> &'b str {
-  if url.get() == match_url {
+  let url = url.get();
+  // nested pages like `/team/:uuid` keep their section selected
+  if url == match_url || (match_url != "/" && url.starts_with(&format!("{}/", match_url))) {
     return INPUT_STYLE_SELECTED;
   }
   INPUT_STYLE
 }
 
 #[component]
-pub fn DashboardHeader() -> impl IntoView {
-  //get today's date
-  let current_now = Local::now();
-  //get the current month's number
-  let month_number = u8::try_from(current_now.month()).unwrap();
-  //convert the current month in short-form
-  let current_month = Month::try_from(month_number).ok().unwrap();
-  //display the month and year in a nice format
-  let display_date = format!("{:?}, {:?}", current_month, current_now.year());
+pub fn DashboardHeader(
+  // the period the report is for, from the url
+  period: Memo<Period>,
+) -> impl IntoView {
+  //display the period in a nice format, e.g. "Q3 2024"
+  let display_date = move || period.get().label();
 
   view! {
     <div class="flex flex-col mt-28 h-48 w-full max-w-[53rem] mx-auto items-center align-middle justify-center px-2">
@@ -75,96 +88,59 @@ pub fn DashboardHeader() -> impl IntoView {
   }
 }
 
-use num_format::{Buffer, Locale};
-use std::rc::Rc;
-use charts_rs::{ BarChart, Color, Series, THEME_DARK };
+use crate::app::dashboard::DashboardStats;
+use crate::app::history::{format_delta, MonthDelta};
+use crate::app::charts::InteractiveBarChart;
+use crate::app::chart_export::{ChartDownloads, DashboardChartKind};
 
 #[component]
-pub fn DashboardChart(persons_data: Vec<Person>) -> impl IntoView {
-  //create the reference counting pointer to our actual persons data to avoid copying by rust
-  let retrieved_persons_data = Rc::new(persons_data.clone());
-
+pub fn DashboardChart(
+  // counted by the database, see `get_dashboard_stats_srv`
+  stats: DashboardStats,
+  // change since last month, from the team history
+  #[prop(default = None)] deltas: Option<MonthDelta>,
+) -> impl IntoView {
   //counting total number of team members
-  let team_count: String = retrieved_persons_data.len().to_string();
+  let team_count: String = stats.members.to_string();
 
-  let mut total_cost: i32 = 0;
-  // 2 vectors for: 1) displaying the titles and 
-  // 2) counting the no for each title
-  let mut data_vec = Vec::new();
-  let mut count_vec= Vec::new();  
+  let total_cost_str = stats.total_cost_label();
 
   //for identifying who is the latest to join
-  let mut latest_member: String = String::new();
-  let mut counter = 0;
-
-  // loop through the returned data
-  for person in persons_data {
-    if  counter == 0 {
-      latest_member = person.name;
-    }
-    // inc compensation total cost
-    total_cost += person.compensation;
-
-    // if the person has a title that isnt in the barcharts we add
-    if !data_vec.contains(&person.title) {
-      // add it to the column
-      data_vec.push(person.title);
-      //also inc the count
-      count_vec.push(1.0);
-    } else {
-      //if title is already added we get the index
-      let index = data_vec
-        .iter()
-        .position(|title| title == &person.title)
-        .unwrap();
-      //we get the no using the index
-      let num_at_index = count_vec[index];
-      // inc by 1
-      count_vec[index] = num_at_index + 1.0;
-    }
-    //increment the counter
-    counter = counter + 1;
-  }
-
-  let mut buf = Buffer::default();
-  buf.write_formatted(&total_cost, &Locale::en);
-  let total_cost_str = format!("${}", buf.as_str());
-
-  let mut bar_series = Series::new(String::new(), count_vec);
-  bar_series.label_show = true;
-
-  let mut bar_chart = BarChart::new_with_theme(vec![bar_series], data_vec, THEME_DARK);
-  bar_chart.font_family = String::from("Noto Sans SC");
-  bar_chart.background_color = Color::transparent();
-  bar_chart.width = 832.0;
-  bar_chart.height = 500.0;
-
-  //show y-axis without dp places
-  bar_chart.y_axis_hidden = true;
+  let latest_member = stats.latest_member.clone().unwrap_or_default();
 
+  let chart_bars = stats.title_bars();
 
   view! {
     <div class="w-full flex flex-col max-w-[64rem] mx-auto pt-8 mb-10">
       <div class="w-full h-20 grid grid-cols-3 gap-4 mx-auto px-2 max-w-[53rem]">
-        <DashboardWidget title="Team Members" value=&team_count/>
-        <DashboardWidget title="Monthly Team Cost" value=&total_cost_str/>
+        <DashboardWidget title="Team Members" value=&team_count
+          delta=deltas.map(|deltas| format_delta(deltas.headcount, false))/>
+        <DashboardWidget title="Monthly Team Cost" value=&total_cost_str
+          delta=deltas.and_then(|deltas| deltas.cost).map(|cost| format_delta(cost, true))/>
         <DashboardWidget title="Just Joined" value=&latest_member/>
       </div> 
       <div class="max-w-[53rem] mx-auto w-full flex flex-col mt-14 pb-12">
-        <div class="w-full max-w-[41rem] h-20 bg-black-200 rounded py-10 px-4 pb-10" inner_html=&bar_chart.svg().unwrap()></div>
+        <InteractiveBarChart bars=chart_bars label=DashboardChartKind::Headcount.title()/>
+        <ChartDownloads chart=DashboardChartKind::Headcount/>
       </div>
     </div>
   }
 }
 
 #[component]
-pub fn DashboardWidget<T>(title: T, value: T) -> impl IntoView
+pub fn DashboardWidget<T>(
+  title: T,
+  value: T,
+  // e.g. "+2 vs last month"
+  #[prop(default = None)] delta: Option<String>,
+) -> impl IntoView
 where T: Into<String>,
 {
   view! {
     <div class="flex flex-col h-36 w-full max-w-[21rem] bg-[#283653] rounded px-10 py-4 justify-center">
       <div class="text-white text-4xl">{value.into()}</div>
       <div class="text-stone-400">{title.into()}</div>
+      {delta.map(|delta| view! { <div class="text-sm text-stone-400">{delta}</div> })}
     </div>
   }
 }
\ No newline at end of file
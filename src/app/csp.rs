@@ -0,0 +1,106 @@
+//content security policy: the browser only runs scripts this app serves itself
+//pages get a fresh nonce for the inline hydration scripts leptos writes, everything else the plain policy
+
+// the policy for a response, `nonce` lets the page's own inline scripts run
+pub fn content_security_policy(nonce: Option<&str>) -> String {
+  let script_src = match nonce {
+    Some(nonce) => format!("script-src 'self' 'nonce-{}' 'wasm-unsafe-eval'", nonce),
+    None => String::from("script-src 'self' 'wasm-unsafe-eval'"),
+  };
+  // cargo leptos watch reloads the page over a websocket on its own port
+  let connect_src = match cfg!(debug_assertions) {
+    true => "connect-src 'self' ws:",
+    false => "connect-src 'self'",
+  };
+  [
+    "default-src 'self'",
+    &script_src,
+    // the charts are inline svg with style attributes
+    "style-src 'self' 'unsafe-inline'",
+    "img-src 'self' data:",
+    connect_src,
+    "object-src 'none'",
+    "base-uri 'self'",
+    "form-action 'self'",
+    "frame-ancestors 'none'",
+  ]
+  .join("; ")
+}
+
+// called once by `App`, before anything renders a script
+pub fn provide_content_security_policy() {
+  #[cfg(feature = "ssr")]
+  {
+    use actix_web::http::header::{self, HeaderValue};
+    use leptos::nonce::{provide_nonce, use_nonce};
+    use leptos::use_context;
+
+    provide_nonce();
+    let nonce = use_nonce().map(|nonce| nonce.to_string());
+    if let (Some(response_options), Ok(policy)) = (
+      use_context::<leptos_actix::ResponseOptions>(),
+      HeaderValue::from_str(&content_security_policy(nonce.as_deref())),
+    ) {
+      response_options.insert_header(header::CONTENT_SECURITY_POLICY, policy);
+    }
+  }
+}
+
+cfg_if::cfg_if! {
+  if #[cfg(feature = "ssr")] {
+    use actix_web::dev::{ServiceRequest, ServiceResponse};
+    use actix_web::http::header::{self, HeaderValue};
+    use actix_web::middleware::Next;
+
+    // wraps the whole app: responses that didn't set their own policy get the one without a nonce
+    pub async fn default_content_security_policy(
+      req: ServiceRequest,
+      next: Next<impl actix_web::body::MessageBody>,
+    ) -> Result<ServiceResponse<impl actix_web::body::MessageBody>, actix_web::Error> {
+      let mut res = next.call(req).await?;
+      if !res.headers().contains_key(header::CONTENT_SECURITY_POLICY) {
+        res.headers_mut().insert(
+          header::CONTENT_SECURITY_POLICY,
+          HeaderValue::from_str(&content_security_policy(None)).expect("the policy is plain ascii"),
+        );
+      }
+      Ok(res)
+    }
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use actix_web::http::header;
+  use actix_web::test::{call_service, init_service, TestRequest};
+  use actix_web::{middleware, web, App, HttpResponse};
+
+  use super::{content_security_policy, default_content_security_policy};
+
+  #[test]
+  fn policy_allows_only_own_and_nonced_scripts() {
+    let policy = content_security_policy(Some("abc123"));
+    let script_src = policy.split("; ").find(|directive| directive.starts_with("script-src")).unwrap();
+    assert_eq!(script_src, "script-src 'self' 'nonce-abc123' 'wasm-unsafe-eval'");
+    assert!(!policy.contains("http"));
+    assert!(!content_security_policy(None).contains("nonce"));
+  }
+
+  #[actix_web::test]
+  async fn middleware_keeps_a_policy_the_response_set() {
+    let app = init_service(
+      App::new()
+        .route("/plain", web::get().to(HttpResponse::Ok))
+        .route("/own", web::get().to(|| async {
+          HttpResponse::Ok().insert_header((header::CONTENT_SECURITY_POLICY, "default-src 'none'")).finish()
+        }))
+        .wrap(middleware::from_fn(default_content_security_policy)),
+    )
+    .await;
+
+    let plain = call_service(&app, TestRequest::get().uri("/plain").to_request()).await;
+    assert_eq!(plain.headers().get(header::CONTENT_SECURITY_POLICY).unwrap(), &content_security_policy(None));
+    let own = call_service(&app, TestRequest::get().uri("/own").to_request()).await;
+    assert_eq!(own.headers().get(header::CONTENT_SECURITY_POLICY).unwrap(), "default-src 'none'");
+  }
+}
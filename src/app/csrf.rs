@@ -0,0 +1,274 @@
+//protection against other sites posting to the server functions with a logged in visitor's cookies
+//the page shell carries a random token in a cookie and a meta tag, mutating calls have to send it back
+//as a header (fetch calls) or a hidden form field (forms submitted before the wasm has loaded)
+use std::future::Future;
+
+use leptos::*;
+use leptos::server_fn::client::{browser::BrowserClient, Client};
+use leptos::server_fn::request::browser::BrowserRequest;
+use leptos::server_fn::response::browser::BrowserResponse;
+
+pub const CSRF_COOKIE: &str = "dashboard_csrf";
+pub const CSRF_HEADER: &str = "x-csrf-token";
+pub const CSRF_FIELD: &str = "csrf_token";
+pub const CSRF_META: &str = "csrf-token";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsrfToken(pub String);
+
+// the token the browser already has, or a new one set as a cookie by this response
+#[cfg(feature = "ssr")]
+fn issue_csrf_token() -> String {
+  use actix_web::http::header::{self, HeaderValue};
+  use actix_web::HttpRequest;
+  use uuid::Uuid;
+
+  let req = use_context::<HttpRequest>();
+  if let Some(cookie) = req.as_ref().and_then(|req| req.cookie(CSRF_COOKIE)) {
+    if !cookie.value().is_empty() {
+      return cookie.value().to_string();
+    }
+  }
+
+  let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+  if let (Some(response_options), Ok(cookie)) = (
+    use_context::<leptos_actix::ResponseOptions>(),
+    HeaderValue::from_str(&csrf_cookie(&token).to_string()),
+  ) {
+    response_options.append_header(header::SET_COOKIE, cookie);
+  }
+  token
+}
+
+// in the browser the token is read back from the meta tag the server rendered
+fn page_csrf_token() -> Option<String> {
+  document()
+    .query_selector(&format!("meta[name=\"{}\"]", CSRF_META))
+    .ok()
+    .flatten()?
+    .get_attribute("content")
+}
+
+// called once by `App`, which renders the token into the meta tag the fetch client reads
+pub fn provide_csrf_token() {
+  cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+      let token = issue_csrf_token();
+    } else {
+      let token = page_csrf_token().unwrap_or_default();
+    }
+  }
+  provide_context(CsrfToken(token));
+}
+
+pub fn use_csrf_token() -> String {
+  use_context::<CsrfToken>().map(|csrf_token| csrf_token.0).unwrap_or_default()
+}
+
+// goes inside every form that posts to a server function
+#[component]
+pub fn CsrfField() -> impl IntoView {
+  view! { <input type="hidden" name=CSRF_FIELD value=use_csrf_token()/> }
+}
+
+// the fetch client of every server function, the browser one plus the token header
+pub struct CsrfClient;
+
+impl<CustErr> Client<CustErr> for CsrfClient {
+  type Request = BrowserRequest;
+  type Response = BrowserResponse;
+
+  fn send(req: Self::Request) -> impl Future<Output = Result<Self::Response, ServerFnError<CustErr>>> + Send {
+    if let Some(token) = page_csrf_token() {
+      req.headers().set(CSRF_HEADER, &token);
+    }
+    <BrowserClient as Client<CustErr>>::send(req)
+  }
+}
+
+cfg_if::cfg_if! {
+  if #[cfg(feature = "ssr")] {
+    use actix_web::body::EitherBody;
+    use actix_web::cookie::{Cookie, SameSite};
+    use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+    use actix_web::error::PayloadError;
+    use actix_web::http::{header, Method};
+    use actix_web::middleware::Next;
+    use actix_web::web::Bytes;
+    use actix_web::HttpResponse;
+    use futures::Stream;
+    use std::pin::Pin;
+
+    use crate::app::api_tokens::bearer_token;
+    use crate::app::auth::SECURE_COOKIES;
+    use crate::app::rest_api::ApiErrorBody;
+    use crate::app::AuthError;
+
+    // readable by the page only through the meta tag, the cookie itself stays http only
+    pub fn csrf_cookie(token: &str) -> Cookie<'static> {
+      Cookie::build(CSRF_COOKIE, token.to_string())
+        .path("/")
+        .http_only(true)
+        .secure(*SECURE_COOKIES)
+        .same_site(SameSite::Lax)
+        .finish()
+    }
+
+    fn is_safe_method(method: &Method) -> bool {
+      matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+    }
+
+    // server functions are all posts, reads included, so all of them need the token
+    fn is_server_fn_path(path: &str) -> bool {
+      leptos::server_fn::actix::server_fn_paths().any(|(server_fn_path, _)| server_fn_path == path)
+    }
+
+    // where the request came from, by the Origin header or else the Referer, none when neither was sent
+    fn same_origin(req: &ServiceRequest) -> Option<bool> {
+      let source = req
+        .headers()
+        .get(header::ORIGIN)
+        .or_else(|| req.headers().get(header::REFERER))?;
+      let source_host = source
+        .to_str()
+        .ok()
+        .and_then(|source| url::Url::parse(source).ok())
+        .and_then(|source| {
+          let host = source.host_str()?.to_string();
+          Some(match source.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host,
+          })
+        });
+      // "null" origins and anything unparsable count as cross site
+      Some(source_host.is_some_and(|source_host| source_host.eq_ignore_ascii_case(req.connection_info().host())))
+    }
+
+    // compares every byte, so the time taken doesn't give away how much of a secret was right
+    pub fn tokens_match(sent: &str, expected: &str) -> bool {
+      !expected.is_empty()
+        && sent.len() == expected.len()
+        && sent.bytes().zip(expected.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+
+    // the header sent by fetch calls, or the hidden field of a plain form post
+    // reading the field consumes the body, so it's put back for the server function
+    async fn sent_token(req: &mut ServiceRequest) -> Option<String> {
+      if let Some(token) = req.headers().get(CSRF_HEADER) {
+        return token.to_str().ok().map(str::to_string);
+      }
+      let is_form = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("application/x-www-form-urlencoded"));
+      if !is_form {
+        return None;
+      }
+
+      let body = req.extract::<Bytes>().await.ok()?;
+      let token = url::form_urlencoded::parse(&body)
+        .find(|(field, _)| field == CSRF_FIELD)
+        .map(|(_, token)| token.into_owned());
+      let replayed: Pin<Box<dyn Stream<Item = Result<Bytes, PayloadError>>>> =
+        Box::pin(futures::stream::once(async move { Ok(body) }));
+      req.set_payload(Payload::from(replayed));
+      token
+    }
+
+    fn csrf_rejected() -> HttpResponse {
+      HttpResponse::Forbidden().json(ApiErrorBody {
+        error: AuthError::CsrfRejected.to_string(),
+        fields: None,
+      })
+    }
+
+    // wraps the whole app: anything but a read must come from this site, and server function calls
+    // must also carry the token of the page they were made from
+    // api token requests are left alone, browsers never attach those by themselves
+    pub async fn require_csrf(
+      mut req: ServiceRequest,
+      next: Next<impl actix_web::body::MessageBody>,
+    ) -> Result<ServiceResponse<EitherBody<impl actix_web::body::MessageBody>>, actix_web::Error> {
+      if is_safe_method(req.method()) || bearer_token(req.request()).is_some() {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+      }
+      if same_origin(&req) == Some(false) {
+        return Ok(req.into_response(csrf_rejected()).map_into_right_body());
+      }
+      if is_server_fn_path(req.path()) {
+        let expected = req.cookie(CSRF_COOKIE).map(|cookie| cookie.value().to_string()).unwrap_or_default();
+        let sent = sent_token(&mut req).await.unwrap_or_default();
+        if !tokens_match(&sent, &expected) {
+          return Ok(req.into_response(csrf_rejected()).map_into_right_body());
+        }
+      }
+      next.call(req).await.map(ServiceResponse::map_into_left_body)
+    }
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use actix_web::http::{header, StatusCode};
+  use actix_web::{middleware, test, web, App};
+  use leptos::server_fn::ServerFn;
+
+  use super::{require_csrf, CSRF_COOKIE, CSRF_FIELD, CSRF_HEADER};
+  use crate::app::AddPersonSrv;
+
+  const HOST: &str = "dashboard.example.com";
+  const TOKEN: &str = "0123456789abcdef";
+
+  fn add_person_post(origin: &str) -> test::TestRequest {
+    test::TestRequest::post()
+      .uri(AddPersonSrv::PATH)
+      .insert_header((header::HOST, HOST))
+      .insert_header((header::ORIGIN, origin))
+      .insert_header((header::COOKIE, format!("{}={}", CSRF_COOKIE, TOKEN)))
+  }
+
+  #[actix_web::test]
+  async fn rejects_cross_site_and_tokenless_posts_to_server_functions() {
+    let app = test::init_service(
+      App::new()
+        .route(AddPersonSrv::PATH, web::post().to(|body: String| async move { body }))
+        .wrap(middleware::from_fn(require_csrf)),
+    )
+    .await;
+    let form = format!("add_person_request[name]=Ann&{}={}", CSRF_FIELD, TOKEN);
+
+    // a form on another site, even one that somehow knows the token
+    let cross_site = add_person_post("https://evil.example.net")
+      .insert_header((header::CONTENT_TYPE, "application/x-www-form-urlencoded"))
+      .set_payload(form.clone())
+      .to_request();
+    assert_eq!(test::call_service(&app, cross_site).await.status(), StatusCode::FORBIDDEN);
+
+    let without_token = add_person_post(&format!("https://{}", HOST))
+      .insert_header((header::CONTENT_TYPE, "application/x-www-form-urlencoded"))
+      .set_payload("add_person_request[name]=Ann")
+      .to_request();
+    assert_eq!(test::call_service(&app, without_token).await.status(), StatusCode::FORBIDDEN);
+
+    // the server function still gets the whole form after the field was read
+    let with_field = add_person_post(&format!("https://{}", HOST))
+      .insert_header((header::CONTENT_TYPE, "application/x-www-form-urlencoded"))
+      .set_payload(form.clone())
+      .to_request();
+    let response = test::call_service(&app, with_field).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(test::read_body(response).await, form.as_bytes());
+
+    let with_header = add_person_post(&format!("https://{}", HOST))
+      .insert_header((CSRF_HEADER, TOKEN))
+      .to_request();
+    assert_eq!(test::call_service(&app, with_header).await.status(), StatusCode::OK);
+
+    let with_bearer = test::TestRequest::post()
+      .uri(AddPersonSrv::PATH)
+      .insert_header((header::AUTHORIZATION, "Bearer dash_id_secret"))
+      .to_request();
+    assert_eq!(test::call_service(&app, with_bearer).await.status(), StatusCode::OK);
+  }
+}
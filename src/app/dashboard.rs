@@ -0,0 +1,185 @@
+//figures of the dashboard, aggregated by the database so no member list or salary goes to the browser
+use charts_rs::{BarChart, Color, HeatmapChart, LineChart, Series, THEME_DARK};
+use serde::{Deserialize, Serialize};
+
+use crate::app::charts::{team_filtered_by_title, tooltip_names, ChartBar};
+use crate::app::compensation::format_amount;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TitleCount {
+  pub title: String,
+  pub members: usize,
+  // the first few, for the chart tooltips
+  pub names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DashboardStats {
+  pub members: usize,
+  // none when some of the pay is hidden from the caller
+  pub total_cost: Option<i64>,
+  pub latest_member: Option<String>,
+  // by title name
+  pub titles: Vec<TitleCount>,
+}
+
+impl DashboardStats {
+  // the total would be wrong if some pay is hidden from this user
+  pub fn total_cost_label(&self) -> String {
+    match self.total_cost {
+      Some(total_cost) => format_amount(total_cost),
+      None => String::from("Restricted"),
+    }
+  }
+
+  // the bars of the headcount chart, each linking to the team list of its title
+  pub fn title_bars(&self) -> Vec<ChartBar> {
+    self.titles
+      .iter()
+      .map(|title| {
+        let mut details = vec![format!("{} {}", title.members, if title.members == 1 { "member" } else { "members" })];
+        details.extend(tooltip_names(&title.names, title.members));
+        ChartBar {
+          label: title.title.clone(),
+          value: title.members as f64,
+          value_label: title.members.to_string(),
+          details,
+          href: Some(team_filtered_by_title(&title.title)),
+        }
+      })
+      .collect()
+  }
+}
+
+// the font of every chart, the png export falls back from it to the one charts-rs bundles
+pub const CHART_FONT: &str = "Noto Sans SC";
+
+// the fields `style_chart` sets, charts-rs gives each chart type its own copy of them
+pub trait ChartFrame {
+  fn frame(&mut self) -> (&mut String, &mut Color, &mut f32, &mut f32);
+}
+
+impl ChartFrame for BarChart {
+  fn frame(&mut self) -> (&mut String, &mut Color, &mut f32, &mut f32) {
+    (&mut self.font_family, &mut self.background_color, &mut self.width, &mut self.height)
+  }
+}
+
+impl ChartFrame for HeatmapChart {
+  fn frame(&mut self) -> (&mut String, &mut Color, &mut f32, &mut f32) {
+    (&mut self.font_family, &mut self.background_color, &mut self.width, &mut self.height)
+  }
+}
+
+impl ChartFrame for LineChart {
+  fn frame(&mut self) -> (&mut String, &mut Color, &mut f32, &mut f32) {
+    (&mut self.font_family, &mut self.background_color, &mut self.width, &mut self.height)
+  }
+}
+
+// the look all dashboard charts share: the font, no background of their own and the width of the page
+pub fn style_chart(chart: &mut impl ChartFrame) {
+  let (font_family, background_color, width, height) = chart.frame();
+  *font_family = String::from(CHART_FONT);
+  *background_color = Color::transparent();
+  *width = 832.0;
+  *height = 500.0;
+}
+
+// members per title as a static chart, the fallback of the interactive one
+pub fn headcount_chart(stats: &DashboardStats) -> BarChart {
+  let titles = stats.titles.iter().map(|title| title.title.clone()).collect();
+  let mut bar_series = Series::new(String::new(), stats.titles.iter().map(|title| title.members as f32).collect());
+  bar_series.label_show = true;
+
+  let mut bar_chart = BarChart::new_with_theme(vec![bar_series], titles, THEME_DARK);
+  style_chart(&mut bar_chart);
+  //show y-axis without dp places
+  bar_chart.y_axis_hidden = true;
+  bar_chart
+}
+
+cfg_if::cfg_if! {
+  if #[cfg(feature = "ssr")] {
+    use crate::app::{CurrentUser, Role};
+
+    // rows of the aggregate queries in `database::get_dashboard_aggregates`
+    #[derive(Debug, Clone, Default, Deserialize)]
+    pub struct TotalsRow {
+      pub members: usize,
+      pub total_cost: i64,
+    }
+
+    #[derive(Debug, Clone, Default, Deserialize)]
+    pub struct TitleRow {
+      pub title: String,
+      pub members: usize,
+      pub names: Vec<String>,
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct DashboardAggregates {
+      pub totals: Option<TotalsRow>,
+      pub titles: Vec<TitleRow>,
+      pub latest_member: Option<String>,
+      // members the caller isn't the manager of
+      pub not_managed: usize,
+    }
+
+    impl DashboardStats {
+      pub fn new(aggregates: DashboardAggregates, current_user: &CurrentUser) -> DashboardStats {
+        // the same rule as `CurrentUser::redact`: the total is only shown when every salary in it is visible
+        let cost_visible = match current_user.role {
+          Role::HrAdmin => true,
+          Role::Manager => aggregates.not_managed == 0,
+          Role::Viewer => aggregates.totals.as_ref().map_or(0, |totals| totals.members) == 0,
+        };
+        let totals = aggregates.totals.unwrap_or_default();
+        DashboardStats {
+          members: totals.members,
+          total_cost: cost_visible.then_some(totals.total_cost),
+          latest_member: aggregates.latest_member,
+          titles: aggregates.titles
+            .into_iter()
+            .map(|row| TitleCount { title: row.title, members: row.members, names: row.names })
+            .collect(),
+        }
+      }
+    }
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use super::{DashboardAggregates, DashboardStats, TitleRow, TotalsRow};
+  use crate::app::{CurrentUser, Role};
+
+  fn user(role: Role) -> CurrentUser {
+    CurrentUser { username: String::from("lee"), role, token_scope: None }
+  }
+
+  fn aggregates(not_managed: usize) -> DashboardAggregates {
+    DashboardAggregates {
+      totals: Some(TotalsRow { members: 3, total_cost: 12000 }),
+      titles: vec![
+        TitleRow { title: String::from("Designer"), members: 1, names: vec![String::from("Cy")] },
+        TitleRow { title: String::from("Engineer"), members: 2, names: vec![String::from("Ann"), String::from("Bob")] },
+      ],
+      latest_member: Some(String::from("Ann")),
+      not_managed,
+    }
+  }
+
+  #[test]
+  fn total_cost_only_when_all_pay_is_visible() {
+    assert_eq!(DashboardStats::new(aggregates(3), &user(Role::HrAdmin)).total_cost, Some(12000));
+    assert_eq!(DashboardStats::new(aggregates(0), &user(Role::Manager)).total_cost, Some(12000));
+    assert_eq!(DashboardStats::new(aggregates(1), &user(Role::Manager)).total_cost, None);
+    assert_eq!(DashboardStats::new(aggregates(0), &user(Role::Viewer)).total_cost, None);
+
+    let stats = DashboardStats::new(aggregates(0), &user(Role::Viewer));
+    let bars = stats.title_bars();
+    assert_eq!(bars[1].details, vec!["2 members", "Ann", "Bob"]);
+    assert_eq!(bars[1].href.as_deref(), Some("/team?title=Engineer"));
+  }
+}
@@ -6,13 +6,20 @@ cfg_if::cfg_if! {
 
     use surrealdb::engine::remote::ws::{Client, Ws};
     use surrealdb::opt::auth::Root;
-    use surrealdb::{Error, Surreal};
+    use surrealdb::{Error, Response, Surreal};
     use once_cell::sync::Lazy;
+    use chrono::NaiveDate;
 
-    use crate::app::{Person, PersonError};
+    use crate::app::api_tokens::StoredApiToken;
+    use crate::app::auth::{Session, User};
+    use crate::app::charts::TOOLTIP_NAMES;
+    use crate::app::dashboard::{DashboardAggregates, TitleRow, TotalsRow};
+    use crate::app::{AuthError, Departure, Person, PersonError};
 
     static DB: Lazy<Surreal<Client>> = Lazy::new(Surreal::init);
 
+    // called once at startup, every request then shares this connection and its sign in
+    // signing in or invalidating per call would log out the requests running alongside it
     pub async fn open_db_connection() {
       DB.connect::<Ws>("127.0.0.1:8000").await;
       DB.signin(Root {
@@ -24,9 +31,7 @@ cfg_if::cfg_if! {
     }
 
     pub async fn get_all_persons() -> Option<Vec<Person>> {
-      open_db_connection().await;
       let get_all_persons = DB.query("SELECT * FROM person ORDER BY joined_date DESC").await;
-      DB.invalidate().await;
 
       match get_all_persons {
         Ok(mut res) => {
@@ -39,12 +44,20 @@ cfg_if::cfg_if! {
         Err(_) => None
       }
     }
+    pub async fn get_person(person_uuid: String) -> Result<Option<Person>, PersonError> {
+      let find_person: Result<Option<Person>, Error> = DB.select(("person", person_uuid)).await;
+
+      // a missing member is Ok(None), an error is the database failing
+      find_person.map_err(|e| {
+        println!("Error in reading a member: {:?}", e);
+        PersonError::PersonRetrievalFailure
+      })
+    }
+
     pub async fn add_person(new_person: Person) -> Option<Person> {
-      open_db_connection().await;
       let results = DB.create(("person", new_person.uuid.clone()))
       .content(new_person)
       .await;
-      DB.invalidate().await;
 
       match results {
         Ok(created_person) => created_person,
@@ -53,21 +66,115 @@ cfg_if::cfg_if! {
           None
         },
       }
-    } 
+    }
+
+    // all or nothing: if any member can't be created none of them are
+    pub async fn add_persons(new_persons: Vec<Person>) -> Result<Vec<Person>, PersonError> {
+      let results = DB
+        .query("BEGIN TRANSACTION;")
+        .query("FOR $person IN $persons { CREATE type::thing('person', $person.uuid) CONTENT $person; };")
+        .query("COMMIT TRANSACTION;")
+        .bind(("persons", new_persons.clone()))
+        .await;
+
+      check_transaction(results, "importing persons", PersonError::PersonCreationFailure)?;
+      Ok(new_persons)
+    }
+
+    // updates and deletes of a bulk change, all saved or none
+    // deleted members move to the departure table, keyed by the same uuid
+    pub async fn apply_bulk_changes(updated_persons: Vec<Person>, departures: Vec<Departure>) -> Result<(), PersonError> {
+      let results = DB
+        .query("BEGIN TRANSACTION;")
+        .query("FOR $person IN $updated { UPDATE type::thing('person', $person.uuid) MERGE $person; };")
+        .query("FOR $departure IN $departures { DELETE type::thing('person', $departure.person.uuid); CREATE type::thing('departure', $departure.person.uuid) CONTENT $departure; };")
+        .query("COMMIT TRANSACTION;")
+        .bind(("updated", updated_persons))
+        .bind(("departures", departures))
+        .await;
+
+      check_transaction(results, "applying bulk change", PersonError::PersonUpdateFailure)
+    }
+
+    // a failed statement only shows up once the response is checked
+    // `action` names what failed in the log, the caller gets `failure`
+    fn check_transaction(results: Result<Response, Error>, action: &str, failure: PersonError) -> Result<(), PersonError> {
+      let results = match results {
+        Ok(response) => response.check(),
+        Err(e) => Err(e),
+      };
+      match results {
+        Ok(_) => Ok(()),
+        Err(e) => {
+          println!("Error in {}: {:?}", action, e);
+          Err(failure)
+        },
+      }
+    }
+
+    pub async fn get_all_departures() -> Option<Vec<Departure>> {
+      let get_all_departures = DB.query("SELECT * FROM departure ORDER BY left_date DESC").await;
+
+      match get_all_departures {
+        Ok(mut res) => res.take(0).ok(),
+        Err(_) => None
+      }
+    }
 
-    pub async fn delete_person(person_uuid: String) -> Result<Option<Person>, PersonError> {
-      open_db_connection().await;
-      let delete_results = DB.delete(("person", person_uuid)).await;
-      DB.invalidate().await;
+    // the dashboard figures grouped by the database, for the team as it stood at the end of `as_of`
+    // members who left after that day count from their departure record, `username` is the caller for the pay check
+    pub async fn get_dashboard_aggregates(username: String, as_of: NaiveDate) -> Result<DashboardAggregates, PersonError> {
+      #[derive(serde::Deserialize)]
+      struct LatestRow {
+        name: String,
+      }
+      #[derive(serde::Deserialize)]
+      struct CountRow {
+        members: usize,
+      }
 
-      match delete_results {
-        Ok(deleted_person) => Ok(deleted_person),
-        Err(_) => Err(PersonError::PersonDeleteFailure)
+      // stored dates start with `YYYY-MM-DD`, so anything before the next day sorts below it
+      let next_day = as_of.succ_opt().unwrap_or(as_of).format("%Y-%m-%d").to_string();
+      let results = DB
+        .query("LET $members = array::concat(
+          (SELECT * FROM person WHERE joined_date < $next_day),
+          (SELECT VALUE person FROM departure WHERE person.joined_date < $next_day AND left_date >= $next_day)
+        );")
+        .query("SELECT count() AS members, math::sum(compensation) AS total_cost FROM $members GROUP ALL;")
+        // only the names a tooltip shows, `members` has the count
+        // grouping only aggregates top-level functions, so the names are sliced in an outer select
+        // `array::flatten` keeps members who share a name, `array::group` would drop the repeats
+        .query("SELECT title, members, array::slice(array::sort(names), 0, $tooltip_names) AS names FROM (
+          SELECT title, count() AS members, array::flatten(name) AS names FROM $members GROUP BY title
+        ) ORDER BY title;")
+        .query("SELECT name, joined_date FROM $members ORDER BY joined_date DESC LIMIT 1;")
+        .query("SELECT count() AS members FROM $members WHERE manager != $username GROUP ALL;")
+        .bind(("username", username))
+        .bind(("next_day", next_day))
+        .bind(("tooltip_names", TOOLTIP_NAMES))
+        .await;
+
+      let query_failure = |e: Error| {
+        println!("Error in aggregating the dashboard: {:?}", e);
+        PersonError::QueryFailure
+      };
+      let mut res = results.map_err(query_failure)?;
+      let totals: Result<Option<TotalsRow>, Error> = res.take(1);
+      let titles: Result<Vec<TitleRow>, Error> = res.take(2);
+      let latest: Result<Option<LatestRow>, Error> = res.take(3);
+      let not_managed: Result<Option<CountRow>, Error> = res.take(4);
+      match (totals, titles, latest, not_managed) {
+        (Ok(totals), Ok(titles), Ok(latest), Ok(not_managed)) => Ok(DashboardAggregates {
+          totals,
+          titles,
+          latest_member: latest.map(|latest| latest.name),
+          not_managed: not_managed.map_or(0, |count| count.members),
+        }),
+        (Err(e), _, _, _) | (_, Err(e), _, _) | (_, _, Err(e), _) | (_, _, _, Err(e)) => Err(query_failure(e)),
       }
     }
 
-    pub async fn update_person(uuid: String, title: String, level: String, compensation: i32) -> Result<Option<Person>, PersonError> {
-      open_db_connection().await;
+    pub async fn update_person(uuid: String, title: String, level: String, compensation: i32, manager: Option<String>) -> Result<Option<Person>, PersonError> {
       //attempt to find the person in the db
       let find_person: Result<Option<Person>, Error> = DB.select(("person", &uuid)).await;
       match find_person {
@@ -83,8 +190,7 @@ cfg_if::cfg_if! {
                   level, 
                   compensation, 
                   found_person.joined_date
-                )).await;
-                let _ = DB.invalidate().await;
+                ).with_manager(manager)).await;
                 match updated_person {
                   Ok(returned_user) => Ok(returned_user),
                   Err(_) => Err(PersonError::PersonUpdateFailure)
@@ -94,10 +200,97 @@ cfg_if::cfg_if! {
           }
         },
         Err(_) => {
-          let _ = DB.invalidate().await;
           Err(PersonError::PersonNotFound)
         }
       }
     }
+
+    pub async fn get_user(username: String) -> Result<Option<User>, AuthError> {
+      let find_user: Result<Option<User>, Error> = DB.select(("user", username)).await;
+
+      match find_user {
+        Ok(found) => Ok(found),
+        Err(_) => Err(AuthError::InvalidCredentials)
+      }
+    }
+
+    pub async fn add_user(new_user: User) -> Result<Option<User>, AuthError> {
+      let results = DB.create(("user", new_user.username.clone()))
+      .content(new_user)
+      .await;
+
+      match results {
+        Ok(created_user) => Ok(created_user),
+        Err(e) => {
+          println!("Error in adding user: {:?}", e);
+          Err(AuthError::UserCreationFailure)
+        },
+      }
+    }
+
+    // sessions are keyed by the sha-256 of their token, the token itself is only in the session cookie
+    pub async fn add_session(token_hash: String, session: Session) -> Result<(), AuthError> {
+      let results: Result<Option<Session>, Error> = DB.create(("session", token_hash))
+      .content(session)
+      .await;
+
+      match results {
+        Ok(Some(_)) => Ok(()),
+        _ => Err(AuthError::SessionFailure),
+      }
+    }
+
+    pub async fn get_session(token_hash: String) -> Option<Session> {
+      let find_session: Result<Option<Session>, Error> = DB.select(("session", token_hash)).await;
+
+      find_session.ok().flatten()
+    }
+
+    pub async fn delete_session(token_hash: String) {
+      let _: Result<Option<Session>, Error> = DB.delete(("session", token_hash)).await;
+    }
+
+    // api tokens are keyed by their id, the part of the token before the secret
+    pub async fn add_api_token(new_token: StoredApiToken) -> Result<(), AuthError> {
+      let results: Result<Option<StoredApiToken>, Error> = DB.create(("api_token", new_token.token_id.clone()))
+      .content(new_token)
+      .await;
+
+      match results {
+        Ok(Some(_)) => Ok(()),
+        Err(e) => {
+          println!("Error in adding api token: {:?}", e);
+          Err(AuthError::TokenFailure)
+        },
+        Ok(None) => Err(AuthError::TokenFailure),
+      }
+    }
+
+    pub async fn get_api_token(token_id: String) -> Option<StoredApiToken> {
+      let find_token: Result<Option<StoredApiToken>, Error> = DB.select(("api_token", token_id)).await;
+
+      find_token.ok().flatten()
+    }
+
+    pub async fn get_all_api_tokens() -> Result<Vec<StoredApiToken>, AuthError> {
+      let get_all_tokens = DB.query("SELECT * FROM api_token ORDER BY created_at DESC").await;
+
+      match get_all_tokens {
+        Ok(mut res) => res.take(0).map_err(|_| AuthError::TokenFailure),
+        Err(_) => Err(AuthError::TokenFailure),
+      }
+    }
+
+    pub async fn delete_api_token(token_id: String) -> Result<Option<StoredApiToken>, AuthError> {
+      let delete_results = DB.delete(("api_token", token_id)).await;
+
+      delete_results.map_err(|_| AuthError::TokenFailure)
+    }
+
+    pub async fn touch_api_token(token_id: String, last_used_at: String) {
+      let _: Result<Option<StoredApiToken>, Error> = DB.update(("api_token", token_id))
+        .merge(serde_json::json!({ "last_used_at": last_used_at }))
+        .await;
+    }
   }
-}
\ No newline at end of file
+}
@@ -4,12 +4,58 @@ use thiserror::Error;
 pub enum PersonError {
   #[error("member not found")]
   PersonNotFound,
+  #[error("failed to read member")]
+  PersonRetrievalFailure,
   #[error("failed to update member")]
   PersonUpdateFailure,
   #[error("failed to create member")]
   PersonCreationFailure,
   #[error("failed to delete member")]
-  PersonDeleteFailure
+  PersonDeleteFailure,
+  #[error("failed to read members")]
+  QueryFailure,
+}
+
+#[derive(Error, Debug)]
+pub enum AuthError {
+  #[error("invalid username or password")]
+  InvalidCredentials,
+  #[error("authentication required")]
+  Unauthenticated,
+  #[error("not allowed for your role")]
+  Forbidden,
+  #[error("failed to create session")]
+  SessionFailure,
+  #[error("failed to hash password")]
+  PasswordHashFailure,
+  #[error("failed to create user")]
+  UserCreationFailure,
+  #[error("single sign-on failed: {0}")]
+  SsoFailure(String),
+  #[error("invalid api token")]
+  InvalidToken,
+  #[error("failed to save api token")]
+  TokenFailure,
+  #[error("request didn't come from this site, reload the page and try again")]
+  CsrfRejected,
+}
+
+#[derive(Error, Debug)]
+pub enum LimitError {
+  #[error("too many requests, try again in {0} seconds")]
+  TooManyRequests(u64),
+  #[error("request body is larger than {0} bytes")]
+  PayloadTooLarge(usize),
+}
+
+#[derive(Error, Debug)]
+pub enum RenderError {
+  #[error("no chart named \"{0}\"")]
+  UnknownChart(String),
+  #[error("no data to chart for this period")]
+  NoData,
+  #[error("failed to render the chart")]
+  RenderFailure,
 }
 
 pub type ErrorMessage = String;
@@ -22,9 +68,11 @@ impl ResponseErrorTrait for ErrorMessage {
   fn create(person_error: PersonError) -> ErrorMessage {
     match person_error {
       PersonError::PersonNotFound => ErrorMessage::from(" member not found"),
+      PersonError::PersonRetrievalFailure => ErrorMessage::from("failed to read member"),
       PersonError::PersonUpdateFailure => ErrorMessage::from("failed to update PersonError"),
       PersonError::PersonCreationFailure => ErrorMessage::from("failed to create person"),
       PersonError::PersonDeleteFailure => ErrorMessage::from("failure to delete member"),
+      PersonError::QueryFailure => ErrorMessage::from("failed to read members"),
     }
   }
 }
\ No newline at end of file
@@ -0,0 +1,251 @@
+//download of the team roster as csv or json, optionally filtered and with only some columns
+//a plain GET so the export form works as a html form and from scripts alike
+pub const EXPORT_PATH: &str = "/api/export";
+
+// every column that can be exported, in file order
+pub const EXPORT_COLUMNS: [&str; 7] = ["uuid", "name", "title", "level", "compensation", "joined_date", "manager"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+  Csv,
+  Json,
+}
+
+impl ExportFormat {
+  pub const ALL: [ExportFormat; 2] = [ExportFormat::Csv, ExportFormat::Json];
+
+  // value of the `format` query param and the file extension
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ExportFormat::Csv => "csv",
+      ExportFormat::Json => "json",
+    }
+  }
+
+  pub fn from_param(format: &str) -> Option<ExportFormat> {
+    ExportFormat::ALL.into_iter().find(|export_format| export_format.as_str() == format)
+  }
+}
+
+cfg_if::cfg_if! {
+  if #[cfg(feature = "ssr")] {
+    use std::convert::Infallible;
+    use std::iter;
+
+    use actix_web::http::header;
+    use actix_web::web::{self, Bytes};
+    use actix_web::{get, HttpResponse};
+    use chrono::{DateTime, Local};
+    use futures::stream::{self, Stream};
+    use serde_json::{Map, Value};
+
+    use crate::app::graphql::PersonFilter;
+    use crate::app::rest_api::ApiError;
+    use crate::app::serv_functions::retrieve_all_persons;
+    use crate::app::{CurrentUser, Person};
+
+    // `format=csv|json`, `column=..` once per column (all of them if left out)
+    // and the optional filters `title`, `level` and `name` (part of the name, any case)
+    pub struct ExportQuery {
+      pub format: ExportFormat,
+      pub columns: Vec<&'static str>,
+      pub filter: PersonFilter,
+    }
+
+    impl ExportQuery {
+      pub fn from_pairs(pairs: Vec<(String, String)>) -> Result<ExportQuery, ApiError> {
+        let mut format = ExportFormat::Csv;
+        let mut columns = Vec::new();
+        let mut filter = PersonFilter::default();
+
+        for (key, value) in pairs {
+          // empty inputs of the export form are sent too, they mean "no filter"
+          if value.trim().is_empty() {
+            continue;
+          }
+          match key.as_str() {
+            "format" => {
+              format = ExportFormat::from_param(&value)
+                .ok_or_else(|| ApiError::InvalidQuery(format!("unknown format \"{}\"", value)))?;
+            }
+            "column" => {
+              let column = EXPORT_COLUMNS
+                .into_iter()
+                .find(|column| *column == value)
+                .ok_or_else(|| ApiError::InvalidQuery(format!("unknown column \"{}\"", value)))?;
+              if !columns.contains(&column) {
+                columns.push(column);
+              }
+            }
+            "title" => filter.title = Some(value),
+            "level" => filter.level = Some(value),
+            "name" => filter.name_contains = Some(value),
+            _ => return Err(ApiError::InvalidQuery(format!("unknown parameter \"{}\"", key))),
+          }
+        }
+
+        if columns.is_empty() {
+          columns = EXPORT_COLUMNS.to_vec();
+        } else {
+          // keep the file order the same whatever order the columns were picked in
+          columns.sort_by_key(|column| EXPORT_COLUMNS.iter().position(|export_column| export_column == column));
+        }
+        Ok(ExportQuery { format, columns, filter })
+      }
+    }
+
+    // e.g. team-20241019-141502.csv
+    pub fn export_filename(format: ExportFormat, now: DateTime<Local>) -> String {
+      format!("team-{}.{}", now.format("%Y%m%d-%H%M%S"), format.as_str())
+    }
+
+    #[get("/api/export")]
+    pub async fn export_persons(
+      current_user: CurrentUser,
+      query: web::Query<Vec<(String, String)>>,
+    ) -> Result<HttpResponse, ApiError> {
+      let export_query = ExportQuery::from_pairs(query.into_inner())?;
+      let persons: Vec<Person> = retrieve_all_persons()
+        .await
+        .into_iter()
+        .map(|person| current_user.redact(person))
+        .filter(|person| export_query.filter.matches(person))
+        .collect();
+
+      let content_type = match export_query.format {
+        ExportFormat::Csv => "text/csv; charset=utf-8",
+        ExportFormat::Json => "application/json",
+      };
+      let filename = export_filename(export_query.format, Local::now());
+      let body = export_stream(persons, export_query.columns, export_query.format);
+
+      Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)))
+        .streaming(body))
+    }
+
+    // the file a member at a time, so only one row is ever serialized ahead of the client
+    // a csv starts with the header line, a json array is opened and closed around the rows
+    pub fn export_stream(
+      persons: Vec<Person>,
+      columns: Vec<&'static str>,
+      format: ExportFormat,
+    ) -> impl Stream<Item = Result<Bytes, Infallible>> {
+      let (head, tail) = match format {
+        ExportFormat::Csv => (csv_line(columns.iter().map(|column| column.to_string())), None),
+        ExportFormat::Json => (Bytes::from_static(b"["), Some(Bytes::from_static(b"]"))),
+      };
+      let rows = persons
+        .into_iter()
+        .enumerate()
+        .map(move |(index, person)| export_row(&person, &columns, format, index == 0));
+      stream::iter(iter::once(head).chain(rows).chain(tail).map(Ok))
+    }
+
+    fn export_row(person: &Person, columns: &[&'static str], format: ExportFormat, first: bool) -> Bytes {
+      let mut values = person_values(person);
+      match format {
+        ExportFormat::Csv => csv_line(columns.iter().map(|column| match &values[*column] {
+          Value::String(value) => csv_safe(value),
+          Value::Null => String::new(),
+          value => value.to_string(),
+        })),
+        ExportFormat::Json => {
+          values.retain(|key, _| columns.contains(&key.as_str()));
+          let row = Value::Object(values).to_string();
+          Bytes::from(match first {
+            true => row,
+            false => format!(",{}", row),
+          })
+        }
+      }
+    }
+
+    // one record with its line break, quoted the csv way
+    fn csv_line(record: impl Iterator<Item = String>) -> Bytes {
+      let mut writer = csv::Writer::from_writer(Vec::new());
+      // writing into a Vec can't fail
+      let _ = writer.write_record(record);
+      Bytes::from(writer.into_inner().unwrap_or_default())
+    }
+
+    // pay the exporting user may not see is null, so it's left empty in a csv
+    fn person_values(person: &Person) -> Map<String, Value> {
+      match serde_json::to_value(person) {
+        Ok(Value::Object(values)) => values,
+        _ => Map::new(),
+      }
+    }
+
+    // spreadsheet apps run a cell starting with one of these as a formula, so text like that is quoted with `'`
+    fn csv_safe(value: &str) -> String {
+      match value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        true => format!("'{}", value),
+        false => value.to_string(),
+      }
+    }
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use futures::executor::block_on;
+  use futures::StreamExt;
+
+  use super::{export_stream, ExportQuery};
+  use crate::app::Person;
+
+  fn pairs(query: &[(&str, &str)]) -> Vec<(String, String)> {
+    query.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+  }
+
+  // the streamed chunks joined back into the file
+  fn exported(persons: Vec<Person>, query: ExportQuery) -> String {
+    let chunks = block_on(export_stream(persons, query.columns, query.format).collect::<Vec<_>>());
+    chunks.into_iter().flatten().map(|chunk| String::from_utf8(chunk.to_vec()).unwrap()).collect()
+  }
+
+  #[test]
+  fn exports_selected_columns_in_file_order() {
+    let persons = vec![
+      Person::new("a1".into(), "Ada".into(), "Engineer, Platform".into(), "Senior".into(), 9000, "2024-01-01".into()),
+      Person::new("g2".into(), "Grace".into(), "Designer".into(), "Junior".into(), 5000, "2024-02-01".into()),
+      Person {
+        compensation: None,
+        ..Person::new("l3".into(), "Linus".into(), "Designer".into(), "Senior".into(), 7000, "2024-03-01".into())
+      }
+      .with_manager(Some("grace".into())),
+    ];
+
+    let query = ExportQuery::from_pairs(pairs(&[("column", "compensation"), ("column", "title"), ("title", "")])).unwrap();
+    assert_eq!(exported(persons.clone(), query), "title,compensation\n\"Engineer, Platform\",9000\nDesigner,5000\nDesigner,\n");
+
+    let query = ExportQuery::from_pairs(pairs(&[("format", "json"), ("column", "name"), ("column", "compensation")])).unwrap();
+    let exported_json: serde_json::Value = serde_json::from_str(&exported(persons.clone(), query)).unwrap();
+    assert_eq!(exported_json, serde_json::json!([
+      { "name": "Ada", "compensation": 9000 },
+      { "name": "Grace", "compensation": 5000 },
+      { "name": "Linus", "compensation": null },
+    ]));
+
+    let query = ExportQuery::from_pairs(pairs(&[("column", "manager"), ("column", "name")])).unwrap();
+    assert_eq!(exported(persons, query), "name,manager\nAda,\nGrace,\nLinus,grace\n");
+
+    assert!(ExportQuery::from_pairs(pairs(&[("column", "password")])).is_err());
+    assert!(ExportQuery::from_pairs(pairs(&[("format", "xlsx")])).is_err());
+  }
+
+  #[test]
+  fn csv_cells_never_start_a_formula() {
+    let persons = vec![
+      Person::new("a1".into(), "=HYPERLINK(\"http://x\")".into(), "+1".into(), "-".into(), 9000, "2024-01-01".into()),
+      Person::new("g2".into(), "@SUM(A1)".into(), "\tTab".into(), "Senior".into(), 5000, "2024-02-01".into()),
+    ];
+    let query = ExportQuery::from_pairs(pairs(&[("column", "name"), ("column", "title"), ("column", "level"), ("column", "compensation")])).unwrap();
+    assert_eq!(
+      exported(persons, query),
+      "name,title,level,compensation\n\"'=HYPERLINK(\"\"http://x\"\")\",'+1,'-,9000\n'@SUM(A1),'\tTab,Senior,5000\n",
+    );
+  }
+}
@@ -0,0 +1,346 @@
+use chrono::NaiveDate;
+use leptos::*;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+const INPUT_STYLE: &str = "w-full h-12 bg-[#333333] pr-4 pl-6 py-4 text-white
+mt-6 outline-none focus:outline-none focus:pl-7 transition-all duration-1000
+ease-in-out";
+
+const FIELD_ERROR_STYLE: &str = "text-red-400 text-xs pt-1 pl-6";
+
+// key used for errors that don't belong to a single field, e.g. a request that won't deserialize
+pub const FORM_ERROR_FIELD: &str = "__form";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldKind {
+  Text,
+  // whole numbers that fit an `i32`, the type of the numeric request fields
+  Number { min: Option<i32>, max: Option<i32> },
+  // one of a fixed list of values
+  Select { options: &'static [&'static str] },
+  // `YYYY-MM-DD`, the value of a date input
+  Date,
+}
+
+// one input of a form, `name` is the serde field name in the request type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldSpec {
+  pub name: &'static str,
+  pub label: &'static str,
+  pub kind: FieldKind,
+  // false for fields that are only set when the record is created
+  pub editable: bool,
+  // an optional field left empty is sent as null
+  pub required: bool,
+}
+
+impl FieldSpec {
+  pub const fn text(name: &'static str, label: &'static str) -> FieldSpec {
+    FieldSpec { name, label, kind: FieldKind::Text, editable: true, required: true }
+  }
+
+  pub const fn number(name: &'static str, label: &'static str, min: Option<i32>, max: Option<i32>) -> FieldSpec {
+    FieldSpec { name, label, kind: FieldKind::Number { min, max }, editable: true, required: true }
+  }
+
+  pub const fn select(name: &'static str, label: &'static str, options: &'static [&'static str]) -> FieldSpec {
+    FieldSpec { name, label, kind: FieldKind::Select { options }, editable: true, required: true }
+  }
+
+  pub const fn date(name: &'static str, label: &'static str) -> FieldSpec {
+    FieldSpec { name, label, kind: FieldKind::Date, editable: true, required: true }
+  }
+
+  pub const fn create_only(self) -> FieldSpec {
+    FieldSpec { editable: false, ..self }
+  }
+
+  pub const fn optional(self) -> FieldSpec {
+    FieldSpec { required: false, ..self }
+  }
+
+  // turn the raw input into the json value serde expects for this field
+  fn parse_input(&self, raw: &str) -> Result<Value, ValidationError> {
+    if !self.required && raw.trim().is_empty() {
+      return Ok(Value::Null);
+    }
+    match self.kind {
+      FieldKind::Number { .. } => raw.trim().parse::<i32>().map(Value::from).map_err(|_| {
+        ValidationError::new("numeric").with_message(format!("{} should be a whole number", self.name).into())
+      }),
+      FieldKind::Select { options } => match options.contains(&raw) {
+        true => Ok(Value::from(raw)),
+        false => Err(ValidationError::new("options").with_message(format!("{} should be one of the options", self.name).into())),
+      },
+      FieldKind::Date => match NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d") {
+        Ok(_) => Ok(Value::from(raw.trim())),
+        Err(_) => Err(ValidationError::new("date").with_message(format!("{} should be a date", self.name).into())),
+      },
+      FieldKind::Text => Ok(Value::from(raw)),
+    }
+  }
+}
+
+// input values of a form bound to the request type `T`
+// `T` is rebuilt from the inputs on every change and checked with its `Validate` rules
+pub struct FormState<T: 'static> {
+  fields: StoredValue<Vec<FieldSpec>>,
+  hidden: StoredValue<Vec<(&'static str, String)>>,
+  values: RwSignal<HashMap<&'static str, String>>,
+  touched: RwSignal<Vec<&'static str>>,
+  validation: Memo<Result<T, ValidationErrors>>,
+  hydrated: ReadSignal<bool>,
+}
+
+impl<T: 'static> Clone for FormState<T> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<T: 'static> Copy for FormState<T> {}
+
+impl<T> FormState<T>
+where
+  T: DeserializeOwned + Validate + PartialEq + 'static,
+{
+  // `hidden` values are part of the request but are not rendered as inputs, e.g. the uuid being edited
+  pub fn new(
+    fields: Vec<FieldSpec>,
+    initial: HashMap<&'static str, String>,
+    hidden: Vec<(&'static str, String)>,
+  ) -> FormState<T> {
+    let fields = store_value(fields);
+    let hidden = store_value(hidden);
+    let values = create_rw_signal(initial);
+    let touched = create_rw_signal(Vec::new());
+    let validation = create_memo(move |_| {
+      values.with(|values| {
+        fields.with_value(|fields| hidden.with_value(|hidden| build_request(fields, values, hidden)))
+      })
+    });
+
+    let hydrated = use_hydrated();
+
+    FormState { fields, hidden, values, touched, validation, hydrated }
+  }
+
+  pub fn is_valid(&self) -> bool {
+    self.validation.with(Result::is_ok)
+  }
+
+  // for the submit button, the plain html form can always be posted before hydration
+  pub fn submit_disabled(&self) -> bool {
+    self.hydrated.get() && !self.is_valid()
+  }
+
+  pub fn value(&self, field: &'static str) -> String {
+    self.values.with_untracked(|values| values.get(field).cloned().unwrap_or_default())
+  }
+
+  pub fn set_value(&self, field: &'static str, value: String) {
+    self.values.update(|values| {
+      values.insert(field, value);
+    });
+  }
+
+  pub fn touch(&self, field: &'static str) {
+    self.touched.update(|touched| {
+      if !touched.contains(&field) {
+        touched.push(field);
+      }
+    });
+  }
+
+  // message of the first failed check on `field`, once the user has left that input
+  pub fn error(&self, field: &'static str) -> Option<String> {
+    if !self.touched.with(|touched| touched.contains(&field)) {
+      return None;
+    }
+    self.validation.with(|validation| field_error(validation, field))
+  }
+
+  // an error of the request as a whole, once the user has left any input
+  pub fn form_error(&self) -> Option<String> {
+    if self.touched.with(Vec::is_empty) {
+      return None;
+    }
+    self.validation.with(|validation| field_error(validation, FORM_ERROR_FIELD))
+  }
+}
+
+fn build_request<T: DeserializeOwned + Validate>(
+  fields: &[FieldSpec],
+  values: &HashMap<&'static str, String>,
+  hidden: &[(&'static str, String)],
+) -> Result<T, ValidationErrors> {
+  let mut validation_errors = ValidationErrors::new();
+  let mut request = Map::new();
+
+  for (name, value) in hidden {
+    request.insert(name.to_string(), Value::from(value.as_str()));
+  }
+  for field in fields {
+    let raw = values.get(field.name).map(String::as_str).unwrap_or_default();
+    match field.parse_input(raw) {
+      Ok(value) => {
+        request.insert(field.name.to_string(), value);
+      }
+      Err(field_error) => validation_errors.add(field.name, field_error),
+    }
+  }
+
+  if !validation_errors.is_empty() {
+    return Err(validation_errors);
+  }
+
+  let request: T = serde_json::from_value(Value::Object(request)).map_err(|e| {
+    let mut validation_errors = ValidationErrors::new();
+    validation_errors.add(FORM_ERROR_FIELD, ValidationError::new("invalid").with_message(e.to_string().into()));
+    validation_errors
+  })?;
+  request.validate()?;
+  Ok(request)
+}
+
+// starting input values for `fields`, read from an existing record such as the `Person` being edited
+pub fn initial_values<R: Serialize>(record: &R, fields: &[FieldSpec]) -> HashMap<&'static str, String> {
+  let record = serde_json::to_value(record).unwrap_or_default();
+  fields
+    .iter()
+    .filter_map(|field| {
+      let value = match record.get(field.name)? {
+        Value::String(value) => value.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+      };
+      Some((field.name, value))
+    })
+    .collect()
+}
+
+// effects only run in the browser, so this stays false in the server render
+// submit buttons should only be disabled once hydrated, a plain html form can always be posted
+pub fn use_hydrated() -> ReadSignal<bool> {
+  let (hydrated, set_hydrated) = create_signal(false);
+  create_effect(move |_| set_hydrated.set(true));
+  hydrated
+}
+
+pub fn field_error<T>(validation: &Result<T, ValidationErrors>, field: &str) -> Option<String> {
+  let validation_errors = validation.as_ref().err()?;
+  let field_errors = validation_errors.field_errors();
+  let first_error = field_errors.get(field)?.first()?;
+  Some(match &first_error.message {
+    Some(message) => message.to_string(),
+    None => format!("{} is invalid", field),
+  })
+}
+
+// renders every field of the form plus its hidden values
+// inputs are named `arg_name[field]` after the server fn argument, so the form also posts as plain html
+#[component]
+pub fn FormFields<T>(form: FormState<T>, arg_name: &'static str) -> impl IntoView
+where
+  T: DeserializeOwned + Validate + PartialEq + 'static,
+{
+  let hidden_inputs = form.hidden.get_value()
+    .into_iter()
+    .map(|(name, value)| view! { <input type="hidden" name=format!("{}[{}]", arg_name, name) value=value/> })
+    .collect_view();
+
+  let inputs = form.fields.get_value()
+    .into_iter()
+    .map(|field| view! { <FormField form field arg_name/> })
+    .collect_view();
+
+  view! {
+    {hidden_inputs}
+    {inputs}
+    <Show when=move || form.form_error().is_some()>
+      <p class=FIELD_ERROR_STYLE>{move || form.form_error()}</p>
+    </Show>
+  }
+}
+
+#[component]
+pub fn FormField<T>(form: FormState<T>, field: FieldSpec, arg_name: &'static str) -> impl IntoView
+where
+  T: DeserializeOwned + Validate + PartialEq + 'static,
+{
+  let name = format!("{}[{}]", arg_name, field.name);
+  let on_input = move |event| form.set_value(field.name, event_target_value(&event));
+  let on_blur = move |_| form.touch(field.name);
+  let initial = form.value(field.name);
+
+  let input = match field.kind {
+    FieldKind::Text => view! {
+      <input type="text" placeholder=field.label class=INPUT_STYLE name=name value=initial required=field.required
+        on:input=on_input on:blur=on_blur/>
+    }.into_view(),
+    FieldKind::Number { min, max } => view! {
+      <input type="number" placeholder=field.label class=INPUT_STYLE name=name value=initial required=field.required
+        min=min max=max on:input=on_input on:blur=on_blur/>
+    }.into_view(),
+    FieldKind::Select { options } => view! {
+      <select class=INPUT_STYLE name=name required=field.required on:change=on_input on:blur=on_blur>
+        <option value="" disabled=field.required selected=initial.is_empty()>{field.label}</option>
+        {options.iter().map(|option| view! {
+          <option value=*option selected=initial == *option>{*option}</option>
+        }).collect_view()}
+      </select>
+    }.into_view(),
+    FieldKind::Date => view! {
+      <input type="date" placeholder=field.label class=INPUT_STYLE name=name value=initial required=field.required
+        on:input=on_input on:blur=on_blur/>
+    }.into_view(),
+  };
+
+  view! {
+    {input}
+    <Show when=move || form.error(field.name).is_some()>
+      <p class=FIELD_ERROR_STYLE>{move || form.error(field.name)}</p>
+    </Show>
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use serde_json::Value;
+  use std::collections::HashMap;
+
+  use super::{build_request, field_error, FieldSpec, FORM_ERROR_FIELD};
+  use crate::app::AddPersonRequest;
+
+  #[test]
+  fn numbers_parse_to_the_request_type() {
+    let fields = [FieldSpec::text("name", "Name"), FieldSpec::number("compensation", "Compensation", None, None)];
+    let values = HashMap::from([("name", String::from("Ann")), ("compensation", String::from("3000000000"))]);
+    let validation = build_request::<AddPersonRequest>(&fields, &values, &[]);
+    assert_eq!(field_error(&validation, "compensation").as_deref(), Some("compensation should be a whole number"));
+
+    // a request that won't deserialize is reported for the form as a whole
+    let values = HashMap::from([("name", String::from("Ann")), ("compensation", String::from("3000"))]);
+    let validation = build_request::<AddPersonRequest>(&fields, &values, &[]);
+    assert!(field_error(&validation, FORM_ERROR_FIELD).is_some_and(|message| message.contains("title")));
+  }
+
+  #[test]
+  fn selects_only_take_their_options() {
+    let level = FieldSpec::select("level", "Level", &["L1", "L2"]);
+    assert_eq!(level.parse_input("L2"), Ok(Value::from("L2")));
+    assert!(level.parse_input("L9").is_err());
+    assert_eq!(level.optional().parse_input(""), Ok(Value::Null));
+  }
+
+  #[test]
+  fn dates_take_the_date_input_format() {
+    let joined = FieldSpec::date("joined_date", "Joined");
+    assert_eq!(joined.parse_input("2024-02-29"), Ok(Value::from("2024-02-29")));
+    assert!(joined.parse_input("2023-02-29").is_err());
+    assert!(joined.parse_input("29/02/2024").is_err());
+  }
+}
@@ -0,0 +1,259 @@
+//graphql endpoint for internal tools: pick the person fields you need and get aggregates in the same round trip
+//mutations go through the same repository functions and validation rules as the server functions
+cfg_if::cfg_if! {
+  if #[cfg(feature = "ssr")] {
+
+    use actix_web::{web, HttpMessage, HttpRequest};
+    use async_graphql::{Context, EmptySubscription, Error, ErrorExtensions, InputObject, Object, Schema, SimpleObject};
+    use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
+    use std::collections::BTreeMap;
+    use validator::{Validate, ValidationErrors};
+
+    use crate::app::serv_functions::{
+      add_new_person, delete_team_person, edit_team_person, find_team_person, retrieve_all_persons,
+    };
+    use crate::app::{AddPersonRequest, AuthError, CurrentUser, DeletePersonRequest, EditPersonRequest, Person, PersonError};
+
+    pub const GRAPHQL_PATH: &str = "/api/graphql";
+
+    pub type DashboardSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+    // all set conditions must match
+    #[derive(InputObject, Default)]
+    pub struct PersonFilter {
+      pub title: Option<String>,
+      pub level: Option<String>,
+      // case-insensitive substring of the name
+      pub name_contains: Option<String>,
+      // pay filters only match pay the caller may see
+      pub min_compensation: Option<i32>,
+      pub max_compensation: Option<i32>,
+    }
+
+    impl PersonFilter {
+      pub fn matches(&self, person: &Person) -> bool {
+        self.title.as_ref().is_none_or(|title| &person.title == title)
+          && self.level.as_ref().is_none_or(|level| &person.level == level)
+          && self.name_contains.as_ref().is_none_or(|name| {
+            person.name.to_lowercase().contains(&name.to_lowercase())
+          })
+          && self.min_compensation.is_none_or(|min| person.compensation.is_some_and(|pay| pay >= min))
+          && self.max_compensation.is_none_or(|max| person.compensation.is_some_and(|pay| pay <= max))
+      }
+    }
+
+    // the pay of all `persons`, none when some of it is hidden from the caller like `DashboardStats::total_cost`
+    fn total_pay<'a>(persons: impl IntoIterator<Item = &'a Person>) -> Option<i64> {
+      persons.into_iter().map(|person| person.compensation.map(i64::from)).sum()
+    }
+
+    #[derive(SimpleObject, Debug, PartialEq)]
+    pub struct GroupStats {
+      // the title or level the members are grouped by
+      pub key: String,
+      pub headcount: i32,
+      // none when some of the pay is hidden from the caller
+      pub total_cost: Option<i64>,
+      pub average_cost: Option<f64>,
+    }
+
+    #[derive(SimpleObject, Debug, PartialEq)]
+    pub struct TeamStats {
+      pub headcount: i32,
+      // none when some of the pay is hidden from the caller
+      pub total_cost: Option<i64>,
+      pub by_title: Vec<GroupStats>,
+      pub by_level: Vec<GroupStats>,
+    }
+
+    impl TeamStats {
+      pub fn from_persons(persons: &[Person]) -> TeamStats {
+        TeamStats {
+          headcount: persons.len() as i32,
+          total_cost: total_pay(persons),
+          by_title: group_stats(persons, |person| &person.title),
+          by_level: group_stats(persons, |person| &person.level),
+        }
+      }
+    }
+
+    // sorted by key so the output is stable
+    fn group_stats(persons: &[Person], key: impl Fn(&Person) -> &String) -> Vec<GroupStats> {
+      let mut groups: BTreeMap<&String, Vec<&Person>> = BTreeMap::new();
+      for person in persons {
+        groups.entry(key(person)).or_default().push(person);
+      }
+      groups
+        .into_iter()
+        .map(|(key, members)| {
+          let headcount = members.len() as i32;
+          let total_cost = total_pay(members);
+          GroupStats {
+            key: key.clone(),
+            headcount,
+            total_cost,
+            average_cost: total_cost.map(|total_cost| total_cost as f64 / f64::from(headcount)),
+          }
+        })
+        .collect()
+    }
+
+    // redacted before filtering, so pay the caller can't see can't be probed with compensation filters
+    async fn filtered_persons(current_user: &CurrentUser, filter: Option<PersonFilter>) -> Vec<Person> {
+      let filter = filter.unwrap_or_default();
+      retrieve_all_persons()
+        .await
+        .into_iter()
+        .map(|person| current_user.redact(person))
+        .filter(|person| filter.matches(person))
+        .collect()
+    }
+
+    // put in the request data by the handler from what `require_session` found
+    fn current_user<'a>(ctx: &Context<'a>) -> async_graphql::Result<&'a CurrentUser> {
+      ctx.data::<CurrentUser>().map_err(|_| Error::new(AuthError::Unauthenticated.to_string()))
+    }
+
+    fn forbidden() -> Error {
+      Error::new(AuthError::Forbidden.to_string())
+    }
+
+    // validation failures carry the per-field errors in `extensions.fields`
+    fn validation_error(validation_errors: ValidationErrors) -> Error {
+      let fields = serde_json::to_value(&validation_errors).unwrap_or_default();
+      Error::new("validation failed").extend_with(|_, extensions| {
+        extensions.set("fields", async_graphql::Value::from_json(fields).unwrap_or_default())
+      })
+    }
+
+    fn person_error(person_error: PersonError) -> Error {
+      Error::new(person_error.to_string())
+    }
+
+    pub struct QueryRoot;
+
+    #[Object]
+    impl QueryRoot {
+      // members, newest first, compensation is null for members the caller doesn't manage
+      async fn persons(&self, ctx: &Context<'_>, filter: Option<PersonFilter>) -> async_graphql::Result<Vec<Person>> {
+        Ok(filtered_persons(current_user(ctx)?, filter).await)
+      }
+
+      async fn person(&self, ctx: &Context<'_>, uuid: String) -> async_graphql::Result<Option<Person>> {
+        let current_user = current_user(ctx)?;
+        let found_person = find_team_person(uuid).await.map_err(person_error)?;
+        Ok(found_person.map(|person| current_user.redact(person)))
+      }
+
+      // headcount and cost, overall and per title/level, of the members matching `filter`
+      // a cost is null when some pay in it is hidden from the caller
+      async fn stats(&self, ctx: &Context<'_>, filter: Option<PersonFilter>) -> async_graphql::Result<TeamStats> {
+        Ok(TeamStats::from_persons(&filtered_persons(current_user(ctx)?, filter).await))
+      }
+    }
+
+    pub struct MutationRoot;
+
+    #[Object]
+    impl MutationRoot {
+      async fn add_person(&self, ctx: &Context<'_>, input: AddPersonRequest) -> async_graphql::Result<Person> {
+        if !current_user(ctx)?.can_add_and_delete() {
+          return Err(forbidden());
+        }
+        input.validate().map_err(validation_error)?;
+        add_new_person(input.name, input.title, input.level, input.compensation, input.manager)
+          .await
+          .ok_or_else(|| person_error(PersonError::PersonCreationFailure))
+      }
+
+      async fn edit_person(&self, ctx: &Context<'_>, input: EditPersonRequest) -> async_graphql::Result<Person> {
+        input.validate().map_err(validation_error)?;
+        let found_person = find_team_person(input.uuid.clone())
+          .await
+          .map_err(person_error)?
+          .ok_or_else(|| person_error(PersonError::PersonNotFound))?;
+        let input = current_user(ctx)?.authorize_edit(&found_person, input).map_err(|_| forbidden())?;
+        edit_team_person(input.uuid, input.title, input.level, input.compensation, input.manager)
+          .await
+          .map_err(person_error)?
+          .ok_or_else(|| person_error(PersonError::PersonUpdateFailure))
+      }
+
+      async fn delete_person(&self, ctx: &Context<'_>, input: DeletePersonRequest) -> async_graphql::Result<Person> {
+        if !current_user(ctx)?.can_add_and_delete() {
+          return Err(forbidden());
+        }
+        input.validate().map_err(validation_error)?;
+        delete_team_person(input.uuid)
+          .await
+          .map_err(person_error)?
+          .ok_or_else(|| person_error(PersonError::PersonNotFound))
+      }
+    }
+
+    pub fn build_schema() -> DashboardSchema {
+      Schema::build(QueryRoot, MutationRoot, EmptySubscription).finish()
+    }
+
+    // mounted at the app root, queries and mutations are both posted
+    pub fn configure(cfg: &mut web::ServiceConfig) {
+      cfg
+        .app_data(web::Data::new(build_schema()))
+        .service(web::resource(GRAPHQL_PATH).route(web::post().to(graphql)));
+    }
+
+    async fn graphql(schema: web::Data<DashboardSchema>, http_req: HttpRequest, req: GraphQLRequest) -> GraphQLResponse {
+      let mut request = req.into_inner();
+      if let Some(current_user) = http_req.extensions().get::<CurrentUser>() {
+        request = request.data(current_user.clone());
+      }
+      schema.execute(request).await.into()
+    }
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use super::{GroupStats, PersonFilter, TeamStats};
+  use crate::app::Person;
+
+  #[test]
+  fn stats_group_filtered_members_by_title_and_level() {
+    let persons = vec![
+      Person::test_member("Ada", "Engineer", "Senior", 9000),
+      Person::test_member("Grace", "Engineer", "Junior", 5000),
+      Person::test_member("Linus", "Designer", "Senior", 7000),
+    ];
+
+    let stats = TeamStats::from_persons(&persons);
+    assert_eq!(stats.headcount, 3);
+    assert_eq!(stats.total_cost, Some(21000));
+    assert_eq!(stats.by_title, vec![
+      GroupStats { key: "Designer".into(), headcount: 1, total_cost: Some(7000), average_cost: Some(7000.0) },
+      GroupStats { key: "Engineer".into(), headcount: 2, total_cost: Some(14000), average_cost: Some(7000.0) },
+    ]);
+    assert_eq!(stats.by_level.len(), 2);
+
+    let filter = PersonFilter { level: Some("Senior".into()), min_compensation: Some(8000), ..Default::default() };
+    let matching: Vec<&Person> = persons.iter().filter(|person| filter.matches(person)).collect();
+    assert_eq!(matching, vec![&persons[0]]);
+  }
+
+  #[test]
+  fn hidden_pay_is_neither_summed_nor_filtered_on() {
+    let persons = vec![
+      Person::test_member("Ada", "Engineer", "Senior", 9000),
+      Person { compensation: None, ..Person::test_member("Grace", "Engineer", "Junior", 5000) },
+      Person::test_member("Linus", "Designer", "Senior", 7000),
+    ];
+
+    let stats = TeamStats::from_persons(&persons);
+    assert_eq!(stats.total_cost, None);
+    assert_eq!(stats.by_title[0].total_cost, Some(7000));
+    assert_eq!((stats.by_title[1].total_cost, stats.by_title[1].average_cost), (None, None));
+
+    let filter = PersonFilter { max_compensation: Some(8000), ..Default::default() };
+    let matching: Vec<&Person> = persons.iter().filter(|person| filter.matches(person)).collect();
+    assert_eq!(matching, vec![&persons[2]]);
+  }
+}
@@ -0,0 +1,275 @@
+//headcount and monthly cost over time, rebuilt from joined dates and the departures of removed members
+//each month is the team as it stood on its last day, at today's pay
+use charts_rs::{LineChart, Series, THEME_DARK};
+use chrono::{Datelike, Months, NaiveDate};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+use crate::app::chart_export::{ChartDownloads, DashboardChartKind};
+use crate::app::compensation::format_amount;
+use crate::app::dashboard::style_chart;
+use crate::app::{Departure, Person};
+
+// choices of the range picker, in months up to the current one
+pub const HISTORY_RANGES: [u32; 3] = [6, 12, 24];
+pub const DEFAULT_HISTORY_RANGE: u32 = 12;
+// longest range the server works out
+pub const MAX_HISTORY_MONTHS: usize = 120;
+// years a month in a url or a request may have, the four digits of a month input
+const MONTH_YEARS: std::ops::RangeInclusive<i32> = 1900..=9999;
+
+const CHART_STYLE: &str = "w-full max-w-[41rem] bg-black-200 rounded py-10 px-4 pb-10";
+
+const RANGE_SELECT_STYLE: &str = "h-10 bg-[#333333] px-4 text-white ml-auto outline-none";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MonthPoint {
+  // `YYYY-MM`
+  pub month: String,
+  pub headcount: usize,
+  pub cost: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TeamHistory {
+  pub points: Vec<MonthPoint>,
+  // some pay in the range is hidden from the caller, so the cost isn't the whole team's
+  pub cost_restricted: bool,
+}
+
+// change from the month before the last one in the range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MonthDelta {
+  pub headcount: i64,
+  // none when the cost is restricted
+  pub cost: Option<i64>,
+}
+
+// first day of a `YYYY-MM` month
+// `%Y` also takes years like `+262142`, which are turned down so no month arithmetic can overflow
+pub fn parse_month(month: &str) -> Option<NaiveDate> {
+  NaiveDate::parse_from_str(&format!("{}-01", month.trim()), "%Y-%m-%d")
+    .ok()
+    .filter(|date| MONTH_YEARS.contains(&date.year()))
+}
+
+// the first days of the `from` and `to` months of a history request
+// none unless both parse, `from` isn't after `to` and the range is at most `MAX_HISTORY_MONTHS` long
+pub fn parse_history_range(from: &str, to: &str) -> Option<(NaiveDate, NaiveDate)> {
+  let (from, to) = (parse_month(from)?, parse_month(to)?);
+  let months = months_between(from, to)?;
+  (months as usize <= MAX_HISTORY_MONTHS).then_some((from, to))
+}
+
+// how many months `from` to `to` span, both counted, none when `to` is in an earlier month
+// the sum is done in `i64`, so any two dates chrono can hold fit
+pub fn months_between(from: NaiveDate, to: NaiveDate) -> Option<u32> {
+  let months = (i64::from(to.year()) - i64::from(from.year())) * 12 + i64::from(to.month()) - i64::from(from.month()) + 1;
+  u32::try_from(months).ok().filter(|months| *months > 0)
+}
+
+pub fn month_key(date: NaiveDate) -> String {
+  date.format("%Y-%m").to_string()
+}
+
+// only the last month chrono can hold has no next month, and it ends on its last day
+pub fn last_day_of_month(first_day: NaiveDate) -> NaiveDate {
+  first_day
+    .checked_add_months(Months::new(1))
+    .and_then(|next_month| next_month.pred_opt())
+    .unwrap_or(NaiveDate::MAX)
+}
+
+// the day of a stored timestamp like `2024-09-20 12:34:56.789 +03:00`
+pub fn date_of(timestamp: &str) -> Option<NaiveDate> {
+  NaiveDate::parse_from_str(timestamp.get(..10)?, "%Y-%m-%d").ok()
+}
+
+// `from` and `to` months of the last `months` months, ending with the month of `today`
+pub fn last_months(months: u32, today: NaiveDate) -> (String, String) {
+  let this_month = today.with_day(1).unwrap_or(today);
+  let first_month = this_month.checked_sub_months(Months::new(months.saturating_sub(1))).unwrap_or(this_month);
+  (month_key(first_month), month_key(this_month))
+}
+
+// on the team on `day`: joined by then and not yet left
+fn on_team(joined_date: &str, left_date: Option<&str>, day: NaiveDate) -> bool {
+  let joined = date_of(joined_date).is_some_and(|joined| joined <= day);
+  let left = left_date.and_then(date_of).is_some_and(|left| left <= day);
+  joined && !left
+}
+
+// the members on the team at the end of `day`, the ones who left since included
+pub fn members_on(persons: &[Person], departures: &[Departure], day: NaiveDate) -> Vec<Person> {
+  let current = persons.iter().filter(|person| on_team(&person.joined_date, None, day));
+  let departed = departures
+    .iter()
+    .filter(|departure| on_team(&departure.person.joined_date, Some(&departure.left_date), day))
+    .map(|departure| &departure.person);
+  current.chain(departed).cloned().collect()
+}
+
+impl TeamHistory {
+  // every month from `from` to `to`, both first days; pay the caller may not see is expected to be redacted
+  pub fn new(persons: &[Person], departures: &[Departure], from: NaiveDate, to: NaiveDate) -> TeamHistory {
+    let members = persons
+      .iter()
+      .map(|person| (person, None))
+      .chain(departures.iter().map(|departure| (&departure.person, Some(departure.left_date.as_str()))));
+    let members: Vec<(&Person, Option<&str>)> = members.collect();
+
+    let mut history = TeamHistory::default();
+    let mut month = from;
+    while month <= to && history.points.len() < MAX_HISTORY_MONTHS {
+      let day = last_day_of_month(month);
+      let mut point = MonthPoint { month: month_key(month), ..MonthPoint::default() };
+      for (person, left_date) in &members {
+        if on_team(&person.joined_date, *left_date, day) {
+          point.headcount += 1;
+          match person.compensation {
+            Some(compensation) => point.cost += i64::from(compensation),
+            None => history.cost_restricted = true,
+          }
+        }
+      }
+      history.points.push(point);
+      match month.checked_add_months(Months::new(1)) {
+        Some(next_month) => month = next_month,
+        None => break,
+      }
+    }
+    history
+  }
+
+  pub fn month_over_month(&self) -> Option<MonthDelta> {
+    let [.., previous, last] = self.points.as_slice() else {
+      return None;
+    };
+    Some(MonthDelta {
+      headcount: last.headcount as i64 - previous.headcount as i64,
+      cost: (!self.cost_restricted).then(|| last.cost - previous.cost),
+    })
+  }
+}
+
+// e.g. "+2 vs last month" or "-$4,000 vs last month"
+pub fn format_delta(delta: i64, as_amount: bool) -> String {
+  let sign = if delta < 0 { "-" } else { "+" };
+  let amount = if as_amount { format_amount(delta.abs()) } else { delta.abs().to_string() };
+  format!("{}{} vs last month", sign, amount)
+}
+
+// headcount on the left axis and cost on the right, the cost only when none of it is hidden
+pub fn history_chart_svg(history: &TeamHistory) -> Option<String> {
+  if history.points.is_empty() {
+    return None;
+  }
+  let months = history.points.iter().map(|point| point.month.clone()).collect();
+  let mut series = vec![Series::new(
+    String::from("Headcount"),
+    history.points.iter().map(|point| point.headcount as f32).collect(),
+  )];
+  if !history.cost_restricted {
+    let mut cost = Series::new(
+      String::from("Monthly Cost"),
+      history.points.iter().map(|point| point.cost as f32).collect(),
+    );
+    cost.y_axis_index = 1;
+    series.push(cost);
+  }
+
+  let mut line_chart = LineChart::new_with_theme(series, months, THEME_DARK);
+  if !history.cost_restricted {
+    let cost_axis = line_chart.y_axis_configs[0].clone();
+    line_chart.y_axis_configs.push(cost_axis);
+  }
+  style_chart(&mut line_chart);
+  // the history line is drawn shorter than the bar charts
+  line_chart.height = 400.0;
+  line_chart.svg().ok()
+}
+
+#[component]
+pub fn TeamHistoryChart(history: TeamHistory, range: RwSignal<u32>) -> impl IntoView {
+  let svg = history_chart_svg(&history);
+
+  view! {
+    <div class="max-w-[53rem] mx-auto w-full flex flex-col pb-12">
+      <div class="flex flex-row items-center mb-6">
+        <div class="pr-4 text-xl">{DashboardChartKind::History.title()}</div>
+        <select class=RANGE_SELECT_STYLE aria-label="range"
+          on:change=move |event| {
+            if let Ok(months) = event_target_value(&event).parse() {
+              range.set(months);
+            }
+          }
+        >
+          {HISTORY_RANGES.into_iter().map(|months| view! {
+            <option value=months selected=move || range.get() == months>{format!("Last {} months", months)}</option>
+          }).collect_view()}
+        </select>
+      </div>
+      <Show when=move || history.cost_restricted>
+        <p class="text-stone-400 mb-6">"Some pay is restricted, so the cost isn't shown"</p>
+      </Show>
+      {svg.map(|svg| view! {
+        <div class=CHART_STYLE inner_html=svg></div>
+        <ChartDownloads chart=DashboardChartKind::History range=Some(range)/>
+      })}
+    </div>
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use chrono::{Datelike, NaiveDate};
+
+  use super::{
+    format_delta, last_months, month_key, months_between, parse_history_range, parse_month, MonthDelta, TeamHistory,
+  };
+  use crate::app::{Departure, Person};
+
+  #[test]
+  fn months_count_who_was_on_the_team_at_their_end() {
+    let persons = vec![
+      Person { joined_date: "2024-01-15 09:00:00.000 +00:00".into(), ..Person::test_member("Ann", "Engineer", "Senior", 5000) },
+      Person { joined_date: "2024-03-31 17:00:00.000 +00:00".into(), ..Person::test_member("Bob", "Engineer", "Senior", 3000) },
+    ];
+    let cy = Person { joined_date: "2023-12-01 09:00:00.000 +00:00".into(), ..Person::test_member("Cy", "Engineer", "Senior", 4000) };
+    let departures = vec![Departure::new(cy, "2024-03-10 12:00:00.000 +00:00".into())];
+    let history = TeamHistory::new(&persons, &departures, parse_month("2024-01").unwrap(), parse_month("2024-04").unwrap());
+
+    let points: Vec<(&str, usize, i64)> = history.points.iter()
+      .map(|point| (point.month.as_str(), point.headcount, point.cost))
+      .collect();
+    assert_eq!(points, vec![("2024-01", 2, 9000), ("2024-02", 2, 9000), ("2024-03", 2, 8000), ("2024-04", 2, 8000)]);
+    assert_eq!(history.month_over_month(), Some(MonthDelta { headcount: 0, cost: Some(0) }));
+    assert_eq!(format_delta(-4000, true), "-$4,000 vs last month");
+  }
+
+  #[test]
+  fn last_months_end_with_the_current_one() {
+    let today = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+    assert_eq!(last_months(6, today), (String::from("2023-09"), String::from("2024-02")));
+    assert_eq!(last_months(1, today), (String::from("2024-02"), String::from("2024-02")));
+    // nothing before the first month chrono can hold
+    let first_month = month_key(NaiveDate::MIN);
+    assert_eq!(last_months(6, NaiveDate::MIN), (first_month.clone(), first_month));
+  }
+
+  #[test]
+  fn months_out_of_range_are_turned_down() {
+    assert_eq!(parse_month("+262142-12"), None);
+    assert_eq!(parse_month("0999-01"), None);
+    assert!(parse_history_range("2015-01", "2024-12").is_some());
+    assert_eq!(parse_history_range("2014-12", "2024-12"), None);
+    assert_eq!(parse_history_range("2024-05", "2024-04"), None);
+    assert_eq!(months_between(NaiveDate::MIN, NaiveDate::MAX), Some(6_291_432));
+    assert_eq!(months_between(NaiveDate::MAX, NaiveDate::MIN), None);
+
+    // the last month chrono can hold still ends the history instead of overflowing
+    let last_month = NaiveDate::MAX.with_day(1).unwrap();
+    let history = TeamHistory::new(&[], &[], last_month, NaiveDate::MAX);
+    assert_eq!(history.points.len(), 1);
+  }
+}
@@ -0,0 +1,166 @@
+//bulk import of team members from a csv file
+//every row is checked with the same `Validate` rules as the add form before anything is saved
+use serde::{Deserialize, Serialize};
+
+use crate::app::{AddPersonRequest, Person};
+
+// header names the file must have, in any order, extra columns are ignored
+pub const IMPORT_COLUMNS: [&str; 4] = ["name", "title", "level", "compensation"];
+
+// one data row of the uploaded file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ImportRow {
+  // line in the file, the header is line 1
+  pub line: u64,
+  // cells in `IMPORT_COLUMNS` order, as written in the file
+  pub values: Vec<String>,
+  // only set when the row passed validation
+  pub request: Option<AddPersonRequest>,
+  pub errors: Vec<String>,
+}
+
+impl ImportRow {
+  pub fn is_valid(&self) -> bool {
+    self.request.is_some()
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ImportSummary {
+  pub imported: Vec<Person>,
+  // rows left out because they failed validation
+  pub skipped: usize,
+}
+
+cfg_if::cfg_if! {
+  if #[cfg(feature = "ssr")] {
+    use csv::{ReaderBuilder, StringRecord, Trim};
+    use validator::Validate;
+
+    // errors that make the whole file unusable come back as `Err`, problems with single rows end up in the row
+    pub fn parse_import_csv(csv_text: &str) -> Result<Vec<ImportRow>, String> {
+      // spreadsheet apps often save csv with a utf-8 byte order mark, which would end up in the first header
+      let csv_text = csv_text.strip_prefix('\u{feff}').unwrap_or(csv_text);
+      let mut reader = ReaderBuilder::new()
+        .trim(Trim::All)
+        .flexible(true)
+        .from_reader(csv_text.as_bytes());
+
+      let headers = reader.headers().map_err(|e| format!("could not read the header row: {}", e))?.clone();
+      let mut column_indexes = Vec::with_capacity(IMPORT_COLUMNS.len());
+      for column in IMPORT_COLUMNS {
+        let index = headers
+          .iter()
+          .position(|header| header.eq_ignore_ascii_case(column))
+          .ok_or_else(|| format!("missing column \"{}\", expected {}", column, IMPORT_COLUMNS.join(",")))?;
+        column_indexes.push(index);
+      }
+
+      // the reader's own line count leaves out blank lines, count them from the byte offset instead
+      // a record's offset is taken before the blank lines in front of it are skipped
+      let line_at = |position: Option<&csv::Position>| {
+        position.map_or(0, |position| {
+          let offset = (position.byte() as usize).min(csv_text.len());
+          let blank_lines = csv_text.as_bytes()[offset..]
+            .iter()
+            .take_while(|byte| matches!(byte, b'\r' | b'\n'))
+            .count();
+          csv_text.as_bytes()[..offset + blank_lines].iter().filter(|byte| **byte == b'\n').count() as u64 + 1
+        })
+      };
+
+      let mut rows = Vec::new();
+      for record in reader.records() {
+        let row = match record {
+          Ok(record) => parse_import_row(line_at(record.position()), &record, &column_indexes),
+          Err(e) => ImportRow {
+            line: line_at(e.position()),
+            values: Vec::new(),
+            request: None,
+            errors: vec![format!("could not read row: {}", e)],
+          },
+        };
+        rows.push(row);
+      }
+      Ok(rows)
+    }
+
+    fn parse_import_row(line: u64, record: &StringRecord, column_indexes: &[usize]) -> ImportRow {
+      let values: Vec<String> = column_indexes
+        .iter()
+        .map(|index| record.get(*index).unwrap_or_default().to_string())
+        .collect();
+
+      let compensation = match values[3].parse::<i32>() {
+        Ok(compensation) => compensation,
+        Err(_) => {
+          return ImportRow {
+            line,
+            values,
+            request: None,
+            errors: vec![String::from("compensation should be numeric")],
+          };
+        }
+      };
+
+      let request = AddPersonRequest::new(values[0].clone(), values[1].clone(), values[2].clone(), compensation);
+      match request.validate() {
+        Ok(()) => ImportRow { line, values, request: Some(request), errors: Vec::new() },
+        Err(validation_errors) => {
+          let field_errors = validation_errors.field_errors();
+          let errors = IMPORT_COLUMNS
+            .iter()
+            .filter_map(|column| field_errors.get(column))
+            .flat_map(|errors| errors.iter())
+            .map(|error| match &error.message {
+              Some(message) => message.to_string(),
+              None => format!("{} is invalid", error.code),
+            })
+            .collect();
+          ImportRow { line, values, request: None, errors }
+        }
+      }
+    }
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use super::parse_import_csv;
+  use crate::app::AddPersonRequest;
+
+  #[test]
+  fn rows_are_validated_and_keep_their_line() {
+    let csv_text = "Compensation,Name,Title,Level,Team\n\
+      5000, Ada ,Engineer,Senior,core\n\
+      \n\
+      lots,Grace,Engineer,Junior,core\n\
+      100,,Designer,Senior,web\n";
+
+    let rows = parse_import_csv(csv_text).unwrap();
+    assert_eq!(rows.len(), 3);
+
+    assert_eq!(rows[0].line, 2);
+    assert_eq!(rows[0].request, Some(AddPersonRequest::new("Ada".into(), "Engineer".into(), "Senior".into(), 5000)));
+
+    assert_eq!(rows[1].line, 4);
+    assert_eq!(rows[1].errors, vec!["compensation should be numeric"]);
+
+    assert_eq!(rows[2].values, vec!["", "Designer", "Senior", "100"]);
+    assert_eq!(rows[2].errors, vec!["name is required", "compensation must be between 2000 and 99999"]);
+    assert!(!rows[2].is_valid());
+  }
+
+  #[test]
+  fn missing_column_rejects_the_file() {
+    let error = parse_import_csv("name,title,compensation\nAda,Engineer,5000\n").unwrap_err();
+    assert!(error.contains("\"level\""));
+  }
+
+  #[test]
+  fn byte_order_mark_is_skipped() {
+    let rows = parse_import_csv("\u{feff}name,title,level,compensation\nAda,Engineer,Senior,5000\n").unwrap();
+    assert_eq!(rows[0].line, 2);
+    assert!(rows[0].is_valid());
+  }
+}
@@ -1,134 +1,148 @@
-use crate::app::{AddPersonRequest, add_person_srv, ToastMessageType, ToastMessage, Toast, Person, EditPersonRequest, edit_person_srv, delete_person_srv, DeletePersonRequest};
+use crate::app::{ToastMessageType, ToastMessage, Toast, Person, AddPersonRequest, EditPersonRequest, ImportRow, ImportPersonsSrv, PreviewImportSrv, IMPORT_COLUMNS, ExportFormat, EXPORT_COLUMNS, EXPORT_PATH, use_person_store, use_current_user, on_action_success, CsrfField};
+use crate::app::form::{FieldSpec, FormFields, FormState, initial_values};
 use leptos::*;
-use validator::Validate;
+use leptos_router::*;
+use std::collections::HashMap;
 use std::rc::Rc;
 
-const INPUT_STYLE: &str = "w-full h-12 bg-[#333333] pr-4 pl-6 py-4 text-white
-mt-6 outline-none focus:outline-none focus:pl-7 transition-all duration-1000
-ease-in-out";
-
 const CANCEL_BUTTON_STYLE: &str = "mt-10 bg-[#555555] px-8 py-2 rounded
 text-white mr-3 transition-all duration-1000 ease-in-out hover:bg-[#666666]";
 
 const ADD_BUTTON_STYLE: &str = "mt-10 bg-[#7734e7] px-8 py-2 rounded text-white
-transition-all duration-1000 ease-in-out hover:bg-[#8448e9]";
+transition-all duration-1000 ease-in-out hover:bg-[#8448e9]
+disabled:bg-[#555555] disabled:cursor-not-allowed";
 
 const NO_ERROR_STYLE: &str = "flex flex-col bg-[#222222] border-t-8 border-[#7734e7]
-px-6 pt-5 h-[29rem] w-full max-w-[36rem] z-50 -mt-2 fixed z-50";
+px-6 pt-5 pb-6 min-h-[29rem] w-full max-w-[36rem] z-50 -mt-2 fixed z-50";
 
 const ERROR_STYLE: &str = "flex flex-col bg-[#222222] border-t-8 border-[#7734e7]
-px-6 pt-5 h-[32rem] w-full max-w-[36rem] z-50 -mt-2 fixed z-50";
+px-6 pt-5 pb-6 min-h-[32rem] w-full max-w-[36rem] z-50 -mt-2 fixed z-50";
 
 const UPDATE_BUTTON_STYLE: &str = "mt-10 bg-[#7734e7] px-8 py-2 rounded
-    text-white transition-all duration-1000 ease-in-out hover:bg-[#8448e9]";
+    text-white transition-all duration-1000 ease-in-out hover:bg-[#8448e9]
+    disabled:bg-[#555555] disabled:cursor-not-allowed";
+
+// the person inputs of the add and edit modals, a new `Person` field only needs adding here
+// edit leaves out the create-only ones
+pub const PERSON_FIELDS: [FieldSpec; 5] = [
+    FieldSpec::text("name", "Name").create_only(),
+    FieldSpec::text("title", "Title"),
+    FieldSpec::text("level", "Level"),
+    FieldSpec::number("compensation", "Compensation", Some(2000), Some(99999)),
+    FieldSpec::text("manager", "Manager username").optional(),
+];
+
+// only hr admins move members to another manager, the server keeps the current one for anyone else
+const MANAGER_FIELD: &str = "manager";
+
+// which modal is open lives in the url query (`/team?modal=edit&uuid=..`) rather than in a signal,
+// so the links opening them and the forms inside also work before hydration or without wasm
+pub const TEAM_PATH: &str = "/team";
+pub const ADD_MODAL: &str = "add";
+pub const EDIT_MODAL: &str = "edit";
+pub const IMPORT_MODAL: &str = "import";
+pub const EXPORT_MODAL: &str = "export";
+
+pub fn modal_href(modal: &str, uuid: Option<&str>) -> String {
+    match uuid {
+        Some(uuid) => format!("{}?modal={}&uuid={}", TEAM_PATH, modal, uuid),
+        None => format!("{}?modal={}", TEAM_PATH, modal),
+    }
+}
+
+// each member's details have their own page, so they can be linked to
+pub fn person_href(uuid: &str) -> String {
+    format!("{}/{}", TEAM_PATH, uuid)
+}
+
+pub fn is_modal_open(modal: &'static str, uuid: Option<String>) -> Signal<bool> {
+    let query = use_query_map();
+    Signal::derive(move || {
+        query.with(|query| {
+            query.get("modal").map(String::as_str) == Some(modal)
+                && query.get("uuid") == uuid.as_ref()
+        })
+    })
+}
+
+// closes the modal and shows the toast once a client side submission succeeds
+// plain form posts are redirected by the server function instead
+fn on_modal_success<I: 'static>(
+    action: Action<I, Result<Person, ServerFnError>>,
+    toast_message_type: ToastMessageType,
+    set_if_show_toast: WriteSignal<bool>,
+    set_toast_message: WriteSignal<ToastMessage>,
+) {
+    let navigate = use_navigate();
+    on_action_success(action, move |_| {
+        navigate(TEAM_PATH, Default::default());
+        set_toast_message.set(ToastMessage::create(toast_message_type));
+        set_if_show_toast.set(true);
+    });
+    // the action outlives the modal, so don't show an old error when it is opened again
+    on_cleanup(move || {
+        action.value().try_set(None);
+    });
+}
+
+// the message to show for a failed submission, if the last one failed
+fn action_error_message<I: 'static, O: 'static>(
+    action: Action<I, Result<O, ServerFnError>>,
+    fallback: &'static str,
+) -> Signal<Option<String>> {
+    Signal::derive(move || {
+        action.value().with(|value| match value {
+            Some(Err(ServerFnError::Args(message))) | Some(Err(ServerFnError::Response(message))) => {
+                Some(message.clone())
+            }
+            Some(Err(_)) => Some(String::from(fallback)),
+            _ => None,
+        })
+    })
+}
 
 #[component]
 pub fn AddPersonModal(
-  set_if_show_modal: WriteSignal<bool>,
-  set_if_show_added: WriteSignal<bool>,
-  set_toast_message: WriteSignal<ToastMessage>,
+    set_if_show_added: WriteSignal<bool>,
+    set_toast_message: WriteSignal<ToastMessage>,
 ) -> impl IntoView {
- 
+    let add_person_action = use_person_store().add_action();
 
-    // field values
-    let (person_name, set_person_name) = create_signal(String::new());
-    let (person_title, set_person_title) = create_signal(String::new());
-    let (person_level, set_person_level) = create_signal(String::new());
-    let (compensation, set_compensation) = create_signal(String::new());
+    on_modal_success(add_person_action, ToastMessageType::NewMemberAdded, set_if_show_added, set_toast_message);
 
     // for error message(s)
-    let (error_message, set_error_message) = create_signal(String::new());
-    let (if_error, set_if_error) = create_signal(false);
+    let error_message = action_error_message(add_person_action, "Error adding Member, Please try again later");
+    let if_error = move || error_message.with(Option::is_some);
 
-    // to close the modal
-    let on_close = move |_| {
-        set_if_show_modal.set(false);
-    };
-
-    // to add the new person
-    let on_click = move |_| {
-        let add_person_request = AddPersonRequest::new(
-            person_name.get(),
-            person_title.get(),
-            person_level.get(),
-            compensation.get().parse::<i32>().expect("Numbers only"),
-        );
-
-        let is_valid = add_person_request.validate();
-
-        match is_valid {
-            Ok(_) => {
-                spawn_local(async move {
-                    let add_result = add_person_srv(add_person_request).await;
-
-                    // we get the result back and do something with it
-                    match add_result {
-                        Ok(_added_person) => {
-                            set_if_show_modal.set(false);
-                            set_toast_message.set(ToastMessage::create(ToastMessageType::NewMemberAdded,));
-                            set_if_show_added.set(true);
-                          }
-                        Err(e) => println!("Error adding: {:?}", e),
-                    };
-                });
-            }
-            Err(_) => {
-                set_if_error.set(true);
-                set_error_message.set(String::from("All fields are required".to_string()))
-            }
-        }
-    };
+    let form = FormState::<AddPersonRequest>::new(PERSON_FIELDS.to_vec(), HashMap::new(), Vec::new());
 
+    // field names follow the server fn argument, so the form also posts as plain html
     view! {
         <div class="flex flex-col w-full h-full z-50 mx-auto items-center align-center">
-            <div class={move || {
-                if if_error.get() { ERROR_STYLE }
+            <ActionForm action=add_person_action class={move || {
+                if if_error() { ERROR_STYLE }
                 else { NO_ERROR_STYLE }
             }}>
-                <Show when=move || { if_error.get() }>
+                <Show when=if_error>
                     <p class="text-white bg-red-500 rounded w-full h-12 px-5 py-3
                         transition-all duration-750 ease-in-out">
                         { error_message.get() }
                     </p>
                 </Show>
+                <CsrfField/>
                 <p class="text-white pt-5">"Add New Employee"</p>
-                <input type="text" placeholder="Name"
-                    class=INPUT_STYLE
-                    value=person_name
-                    on:input=move |event| {
-                        set_person_name.set(event_target_value(&event));
-                    }
-                />
-                <input type="text" placeholder="Title"
-                    class=INPUT_STYLE
-                    value=person_title
-                    on:input=move |event| {
-                        set_person_title.set(event_target_value(&event));
-                    }
-                />
-                <input type="text" placeholder="Level"
-                    class=INPUT_STYLE
-                    value=person_level
-                    on:input=move |event| {
-                        set_person_level.set(event_target_value(&event));
-                    }
-                />
-                <input type="text" placeholder="Compensation"
-                    class=INPUT_STYLE
-                    value=compensation
-                    on:input=move |event| {
-                        set_compensation.set(event_target_value(&event));
-                    }
-                />
+                <FormFields form arg_name="add_person_request"/>
                 <div class="flex flex-row w-full items-right justify-right">
-                    <button on:click=on_close class=CANCEL_BUTTON_STYLE>
+                    <A href=TEAM_PATH class=CANCEL_BUTTON_STYLE>
                         "Cancel"
-                    </button>
-                    <button on:click=on_click class=ADD_BUTTON_STYLE>
+                    </A>
+                    <button type="submit" class=ADD_BUTTON_STYLE
+                        disabled=move || form.submit_disabled()
+                    >
                         "Add"
                     </button>
                 </div>
-            </div>
+            </ActionForm>
         </div>
     }
 }
@@ -136,192 +150,342 @@ pub fn AddPersonModal(
 #[component]
 pub fn EditPersonModal(
     person: Rc<Person>,
-    set_if_show_edit_modal: WriteSignal<bool>,
     set_if_show_toast: WriteSignal<bool>,
     set_toast_message: WriteSignal<ToastMessage>,
-    person_resource: Resource<(), Result<Vec<Person>, ServerFnError>>,
 ) -> impl IntoView {
-    let (person_name, _set_person_name) = create_signal(person.name.clone());
-    let (person_title , set_person_title) = create_signal(person.title.clone());
-    let (person_level , set_person_level) = create_signal(person.level.clone());
-    let (compensation ,set_person_compensation) = create_signal(format!("{}", person.compensation));
+    let edit_person_action = use_person_store().edit_action();
 
-    // for error messages
-    let (error_message, set_error_message) = create_signal(String::new());
-    let (if_error, set_if_error) = create_signal(false);
- 
-    //handler to close the modal
-    let on_close = move |_| {
-        set_if_show_edit_modal.set(false);
-    };
+    on_modal_success(edit_person_action, ToastMessageType::MemberUpdated, set_if_show_toast, set_toast_message);
 
-    //to update the person
-    let on_click = move |_| {
-        let uuid = person.uuid.clone();
-        //validate if compensation is a valid no
-        let validated_compensation = compensation.get().parse::<i32>();
-        //if no issues with the compensation
-        if let Ok(_ok_compensation) = validated_compensation {
-            let edit_person_request = EditPersonRequest::new(uuid, person_title.get(), person_level.get(), compensation.get().parse::<i32>().expect("Numbers only"));
-
-            let is_valid = edit_person_request.validate();
-
-            match is_valid {
-                Ok(_) => {
-                    let _ = spawn_local(async move {
-                        //call to the srv fn
-                        let edit_result = edit_person_srv(edit_person_request).await;
-
-                        //do smthng with the person|error result
-                        match edit_result {
-                            Ok(_edited_person) => {
-                                person_resource.refetch();
-                                set_if_show_edit_modal.set(false);
-                                set_toast_message.set(ToastMessage::create(ToastMessageType::MemberUpdated,));
-                                set_if_show_toast.set(true);
-                            },
-                            Err(_e) => {
-                                set_if_error.set(true);
-                                set_error_message.set(String::from("Error updating Member, Please try again later"))
-                            }
-                        };
-                    });
-                },
-                Err(_e) => {
-                    set_if_error.set(true);
-                    set_error_message.set(String::from("All fields are required"))
-                }
-            }
-        } else {
-            set_if_error.set(true);
-            set_error_message.set(String::from("Compensation should be Numeric"))    
-        }
-    };
+    // for error messages
+    let error_message = action_error_message(edit_person_action, "Error updating Member, Please try again later");
+    let if_error = move || error_message.with(Option::is_some);
+
+    let is_hr_admin = use_current_user().with_untracked(|current_user| {
+        current_user.as_ref().is_some_and(|current_user| current_user.can_add_and_delete())
+    });
+    let edit_fields: Vec<FieldSpec> = PERSON_FIELDS
+        .into_iter()
+        .filter(|field| field.editable && (is_hr_admin || field.name != MANAGER_FIELD))
+        .collect();
+    let form = FormState::<EditPersonRequest>::new(
+        edit_fields.clone(),
+        initial_values(person.as_ref(), &edit_fields),
+        vec![("uuid", person.uuid.clone())],
+    );
 
     view! {
         <div class="flex flex-col absolute top-20 left-0 w-full h-full z-50 mx-auto items-center">
 
-        <div class={ move || {
-            if if_error.get() { ERROR_STYLE }
+        <ActionForm action=edit_person_action class={ move || {
+            if if_error() { ERROR_STYLE }
             else { NO_ERROR_STYLE }
         }}>
 
-            <Show when=move || { if_error.get() }>
+            <Show when=if_error>
                 <p class="text-white bg-red-500 rounded w-full h-12 px-5
                     py-3 transition-all duration-750 ease-in-out">
                     { error_message.get() }
                 </p>
             </Show>
-            <p class="text-white pt-5 text-4xl mb-10">{person_name}</p>
+            <p class="text-white pt-5 text-4xl mb-10">{&person.name}</p>
 
-            <input type="text" placeholder="Title" class=INPUT_STYLE
-                value=person_title.get()
-                on:input=move |event| {
-                    set_person_title.set(event_target_value(&event));
-                }
-            />
-            <input type="text" placeholder="Level" class=INPUT_STYLE
-                value=person_level.get()
-                on:input=move |event| {
-                    set_person_level.set(event_target_value(&event));
-                }
-            />
-            <input type="text" placeholder="Compensation" class=INPUT_STYLE
-                value=compensation.get()
-                on:input=move |event| {
-                    set_person_compensation.set(event_target_value(&event));
-                }
-            />
+            <CsrfField/>
+            <FormFields form arg_name="edit_person_request"/>
 
             <div class="flex flex-row w-full items-right justify-right mt-3">
 
-                <button on:click=on_close class=CANCEL_BUTTON_STYLE>
+                <A href=TEAM_PATH class=CANCEL_BUTTON_STYLE>
                     "Cancel"
-                </button>
-                <button on:click=on_click class=UPDATE_BUTTON_STYLE>
+                </A>
+                <button type="submit" class=UPDATE_BUTTON_STYLE
+                    disabled=move || form.submit_disabled()
+                >
                     "Update"
                 </button>
             </div>
-        </div>
+        </ActionForm>
         </div>
     }
 }
 
-const INFO_STYLE: &str = "w-full h-12 pr-4 mt-6 flex flex-col outline-none focus:outline-none focus:pl-7 transition-all duration-1000 ease-in-out";
-const INFO_TITLE_STYLE: &str = "text-stone-400 text-xs";
-const INFO_VALUE_STYLE: &str = "text-white";
-const CLOSE_BUTTON_STYLE: &str = "mt-10 bg-[#555555] px-8 py-2 rounded text-white mr-3 transition-all duration-1000 ease-in-out hover:[#666666]";
-const DELETE_BUTTON_STYLE: &str = "mt-10 bg-[#555555] px-8 py-2 rounded text-white mr-3 transition-all duration-1000 ease-in-out hover:bg-red-500";
-const MODAL_STYLE: &str = "flex flex-col bg-[#222222] border-t-8 border-[#7734e7] px-6 pt-5 h-[28rem] w-full max-w-[36rem] z-50 -mt-2 fixed top-20 z-50";
+const IMPORT_STYLE: &str = "flex flex-col bg-[#222222] border-t-8 border-[#7734e7]
+px-6 pt-5 pb-6 w-full max-w-[52rem] max-h-[80vh] overflow-y-auto z-50 -mt-2 fixed";
+
+const FILE_INPUT_STYLE: &str = "w-full mt-6 text-stone-400 file:mr-4 file:px-6 file:py-2 file:rounded
+file:border-0 file:bg-[#333333] file:text-white";
 
+const PREVIEW_CELL_STYLE: &str = "pr-4 py-1 align-top";
+
+// the file is read in the browser and sent as text, the server parses and validates it for the preview
+// importing sends the same text again, so only what the server checked is saved
 #[component]
-pub fn ShowPersonModal(
-    person: Rc<Person>,
-    set_if_show_info_modal: WriteSignal<bool>,
-    set_if_show_deleted: WriteSignal<bool>,
-    person_resource: Resource<(), Result<Vec<Person>, ServerFnError>>,
+pub fn ImportPersonsModal(
+    set_if_show_toast: WriteSignal<bool>,
     set_toast_message: WriteSignal<ToastMessage>,
 ) -> impl IntoView {
-    let this_person = person.clone();
-    // to close the modal
-    let on_close = move |_| {
-        set_if_show_info_modal.set(false);
+    let import_action = use_person_store().import_action();
+    let preview_action = create_server_action::<PreviewImportSrv>();
+    let (csv_text, set_csv_text) = create_signal(String::new());
+    let (file_error, set_file_error) = create_signal(None::<String>);
+
+    let navigate = use_navigate();
+    on_action_success(import_action, move |import_summary| {
+        navigate(TEAM_PATH, Default::default());
+        set_toast_message.set(ToastMessage::create(ToastMessageType::MembersImported {
+            imported: import_summary.imported.len(),
+            skipped: import_summary.skipped,
+        }));
+        set_if_show_toast.set(true);
+    });
+    on_cleanup(move || {
+        import_action.value().try_set(None);
+    });
+
+    let preview_error = action_error_message(preview_action, "Error reading the file, Please check it is a csv file");
+    let import_error = action_error_message(import_action, "Error importing Members, Please try again later");
+    let error_message = Signal::derive(move || file_error.get().or(preview_error.get()).or(import_error.get()));
+
+    let rows = Signal::derive(move || {
+        preview_action.value().with(|value| match value {
+            Some(Ok(rows)) => rows.clone(),
+            _ => Vec::new(),
+        })
+    });
+    let valid_count = Signal::derive(move || rows.with(|rows| rows.iter().filter(|row| row.is_valid()).count()));
+
+    let on_file_change = move |event: ev::Event| {
+        let input: web_sys::HtmlInputElement = event_target(&event);
+        let Some(file) = input.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        set_file_error.set(None);
+        import_action.value().set(None);
+        spawn_local(async move {
+            match wasm_bindgen_futures::JsFuture::from(file.text()).await {
+                Ok(text) => {
+                    let text = text.as_string().unwrap_or_default();
+                    set_csv_text.set(text.clone());
+                    preview_action.dispatch(PreviewImportSrv { csv_text: text });
+                }
+                Err(_) => set_file_error.set(Some(String::from("The file could not be read"))),
+            }
+        });
     };
 
-    //to perfom deletion
-    let on_click_delete = move |_| {
-        let to_delete_uuid = format!("{}", &this_person.uuid);
+    let on_import = move |_| {
+        import_action.dispatch(ImportPersonsSrv { csv_text: csv_text.get_untracked() });
+    };
+
+    view! {
+        <div class="flex flex-col w-full h-full z-50 mx-auto items-center align-center">
+            <div class=IMPORT_STYLE>
+                <Show when=move || error_message.with(Option::is_some)>
+                    <p class="text-white bg-red-500 rounded w-full px-5 py-3
+                        transition-all duration-750 ease-in-out">
+                        { error_message.get() }
+                    </p>
+                </Show>
+                <p class="text-white pt-5">"Import Members"</p>
+                <p class="text-stone-400 text-xs pt-2">
+                    {format!("A csv file with a header row containing the columns: {}", IMPORT_COLUMNS.join(", "))}
+                </p>
+                <input type="file" accept=".csv,text/csv" class=FILE_INPUT_STYLE on:change=on_file_change/>
+
+                <Show when=move || preview_action.pending().get()>
+                    <p class="text-stone-400 pt-4">"Reading file ..."</p>
+                </Show>
+                <Show when=move || !rows.with(Vec::is_empty)>
+                    <p class="text-white pt-6">
+                        {move || format!("{} of {} rows can be imported", valid_count.get(), rows.with(Vec::len))}
+                    </p>
+                    <table class="w-full mt-3 text-sm text-left">
+                        <thead class="text-stone-400 text-xs">
+                            <tr>
+                                <th class=PREVIEW_CELL_STYLE>"Line"</th>
+                                {IMPORT_COLUMNS.iter().map(|column| view! {
+                                    <th class=PREVIEW_CELL_STYLE>{*column}</th>
+                                }).collect_view()}
+                                <th class=PREVIEW_CELL_STYLE>"Errors"</th>
+                            </tr>
+                        </thead>
+                        <tbody>
+                            {move || rows.get().into_iter().map(|row| view! { <ImportPreviewRow row/> }).collect_view()}
+                        </tbody>
+                    </table>
+                </Show>
 
-        let delete_person_request = DeletePersonRequest::new(to_delete_uuid);
+                <div class="flex flex-row w-full items-right justify-right">
+                    <A href=TEAM_PATH class=CANCEL_BUTTON_STYLE>
+                        "Cancel"
+                    </A>
+                    <button type="button" class=ADD_BUTTON_STYLE on:click=on_import
+                        disabled=move || valid_count.get() == 0 || preview_action.pending().get() || import_action.pending().get()
+                    >
+                        {move || format!("Import {} Members", valid_count.get())}
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+}
 
-        let _ = spawn_local(async move {
-            let delete_result = delete_person_srv(delete_person_request).await;
+#[component]
+fn ImportPreviewRow(row: ImportRow) -> impl IntoView {
+    let row_style = if row.is_valid() { "text-white" } else { "text-red-400" };
 
-            match delete_result {
-                Ok(_deleted_person) => {
-                    person_resource.refetch();
-                    set_toast_message.set(ToastMessage::create(ToastMessageType::MemberDeleted));
-                    set_if_show_deleted.set(true);
-                    set_if_show_info_modal.set(false);
-                }
-                Err(e) => println!("Error deleting = {:?}", e),
-            };
-        });
+    view! {
+        <tr class=row_style>
+            <td class=PREVIEW_CELL_STYLE>{row.line}</td>
+            {(0..IMPORT_COLUMNS.len()).map(|index| view! {
+                <td class=PREVIEW_CELL_STYLE>{row.values.get(index).cloned().unwrap_or_default()}</td>
+            }).collect_view()}
+            <td class=PREVIEW_CELL_STYLE>{row.errors.join(", ")}</td>
+        </tr>
+    }
+}
+
+const EXPORT_INPUT_STYLE: &str = "w-full h-12 bg-[#333333] pr-4 pl-6 py-4 text-white mt-4 outline-none";
+
+// a plain GET form, the browser downloads the file the server answers with and the page stays put
+#[component]
+pub fn ExportPersonsModal() -> impl IntoView {
+    view! {
+        <div class="flex flex-col w-full h-full z-50 mx-auto items-center align-center">
+            <form method="get" action=EXPORT_PATH class=NO_ERROR_STYLE>
+                <p class="text-white pt-5">"Export Members"</p>
+                <select name="format" class=EXPORT_INPUT_STYLE>
+                    {ExportFormat::ALL.iter().map(|format| view! {
+                        <option value=format.as_str()>{format.as_str().to_uppercase()}</option>
+                    }).collect_view()}
+                </select>
+                <p class="text-stone-400 text-xs pt-6">"Columns"</p>
+                <div class="flex flex-row flex-wrap text-white">
+                    {EXPORT_COLUMNS.iter().map(|column| view! {
+                        <label class="pr-6 pt-2">
+                            <input type="checkbox" name="column" value=*column checked class="mr-2"/>
+                            {*column}
+                        </label>
+                    }).collect_view()}
+                </div>
+                <p class="text-stone-400 text-xs pt-6">"Only members matching (leave empty for everyone)"</p>
+                <input type="text" name="title" placeholder="Title" class=EXPORT_INPUT_STYLE/>
+                <input type="text" name="level" placeholder="Level" class=EXPORT_INPUT_STYLE/>
+                <input type="text" name="name" placeholder="Name contains" class=EXPORT_INPUT_STYLE/>
+                <div class="flex flex-row w-full items-right justify-right">
+                    <A href=TEAM_PATH class=CANCEL_BUTTON_STYLE>
+                        "Close"
+                    </A>
+                    <button type="submit" class=ADD_BUTTON_STYLE>
+                        "Download"
+                    </button>
+                </div>
+            </form>
+        </div>
+    }
+}
+
+const INFO_STYLE: &str = "w-full h-12 pr-4 mt-6 flex flex-col outline-none focus:outline-none focus:pl-7 transition-all duration-1000 ease-in-out";
+const INFO_TITLE_STYLE: &str = "text-stone-400 text-xs";
+const INFO_VALUE_STYLE: &str = "text-white";
+const CLOSE_BUTTON_STYLE: &str = "mt-10 bg-[#555555] px-8 py-2 rounded text-white mr-3 transition-all duration-1000 ease-in-out hover:[#666666]";
+const DELETE_BUTTON_STYLE: &str = "mt-10 bg-[#555555] px-8 py-2 rounded text-white mr-3 transition-all duration-1000 ease-in-out hover:bg-red-500";
+const DETAILS_STYLE: &str = "flex flex-col bg-[#222222] border-t-8 border-[#7734e7] px-6 pt-5 pb-6 w-full max-w-[36rem]";
+
+#[component]
+pub fn PersonDetails(person: Rc<Person>) -> impl IntoView {
+    let delete_person_action = use_person_store().delete_action();
+
+    // this page goes away with the member, so the team page shows the toast through its flash param
+    let navigate = use_navigate();
+    on_action_success(delete_person_action, move |_| {
+        let flash_key = ToastMessageType::MemberDeleted.flash_key().unwrap_or_default();
+        navigate(&format!("{}?flash={}", TEAM_PATH, flash_key), Default::default());
+    });
+    on_cleanup(move || {
+        delete_person_action.value().try_set(None);
+    });
+    let error_message = action_error_message(delete_person_action, "Error deleting Member, Please try again later");
+
+    // the buttons follow the same role checks as the server functions behind them
+    let current_user = use_current_user();
+    let managed_person = person.clone();
+    let edit_href = store_value(modal_href(EDIT_MODAL, Some(&person.uuid)));
+    let can_edit = move || current_user.with(|current_user| {
+        current_user.as_ref().is_some_and(|current_user| current_user.can_edit(&managed_person))
+    });
+    let can_delete = move || current_user.with(|current_user| {
+        current_user.as_ref().is_some_and(|current_user| current_user.can_add_and_delete())
+    });
+    let compensation = match person.compensation {
+        Some(compensation) => compensation.to_string(),
+        None => String::from("Restricted"),
     };
 
     view! {
-        <div class="flex flex-col absolute top-20 left-0 w-full h-full z-49 bg-[#222222]/[.06]">
-            <div class="flex flex-col absolute top-20 left-0 w-full h-full z-50 mx-auto items-center align-middle">
-                <div class=MODAL_STYLE>
-                    <p class="text-white pt-5 text-4xl mb-2 mt-2">
-                    {&person.name}
+        <div class="flex flex-col w-full mx-auto items-center align-middle">
+            <div class=DETAILS_STYLE>
+                <Show when=move || error_message.with(Option::is_some)>
+                    <p class="text-white bg-red-500 rounded w-full h-12 px-5 py-3
+                        transition-all duration-750 ease-in-out">
+                        { error_message.get() }
                     </p>
-                    <div class=INFO_STYLE>
+                </Show>
+                <p class="text-white pt-5 text-4xl mb-2 mt-2">
+                {&person.name}
+                </p>
+                <div class=INFO_STYLE>
+                <div class=INFO_TITLE_STYLE>
+                "Title"
+                </div>
+                <div class=INFO_VALUE_STYLE>
+                {&person.title}
+                </div>
+                </div>
+                <div class=INFO_STYLE>
+                <div class=INFO_TITLE_STYLE>
+                "Level"
+                </div>
+                <div class=INFO_VALUE_STYLE>
+                {&person.level}
+                </div>
+                </div>
+                <div class=INFO_STYLE>
                     <div class=INFO_TITLE_STYLE>
-                    "Title"
+                    "Compensation"
                     </div>
                     <div class=INFO_VALUE_STYLE>
-                    {&person.title}
+                    {compensation}
                     </div>
+                </div>
+                <div class=INFO_STYLE>
+                    <div class=INFO_TITLE_STYLE>
+                    "Manager"
                     </div>
-                    <div class=INFO_STYLE>
-                        <div class=INFO_TITLE_STYLE>
-                        "Compensation"
-                        </div>
-                        <div class=INFO_VALUE_STYLE>
-                        {format!("{:?}", &person.compensation)}
-                        </div>
-                    </div>
-                    <div class="flex flex-row w-full items-right justify-end mt-3">
-                        <button on:click= on_close class=CLOSE_BUTTON_STYLE>
-                        "Close"
-                        </button>
-                        <button on:click= on_click_delete class=DELETE_BUTTON_STYLE>
-                        "Delete"
-                        </button>
+                    <div class=INFO_VALUE_STYLE>
+                    {person.manager.clone().unwrap_or_else(|| String::from("-"))}
                     </div>
                 </div>
+                <ActionForm action=delete_person_action class="flex flex-row w-full items-right justify-end mt-3">
+                    <CsrfField/>
+                    <input type="hidden" name="delete_person_request[uuid]" value=&person.uuid />
+                    <A href=TEAM_PATH class=CLOSE_BUTTON_STYLE>
+                    "Close"
+                    </A>
+                    <Transition fallback=|| ()>
+                        <Show when=can_edit.clone()>
+                            <A href=edit_href.get_value() class=CLOSE_BUTTON_STYLE>
+                            "Edit"
+                            </A>
+                        </Show>
+                        <Show when=can_delete>
+                            <button type="submit" class=DELETE_BUTTON_STYLE>
+                            "Delete"
+                            </button>
+                        </Show>
+                    </Transition>
+                </ActionForm>
             </div>
         </div>
     }
-}
\ No newline at end of file
+}
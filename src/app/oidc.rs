@@ -0,0 +1,508 @@
+//single sign-on through the company's openid connect provider, next to the local passwords
+//authorization code flow with pkce, the provider's groups decide the dashboard role
+use leptos::*;
+
+pub const OIDC_LOGIN_PATH: &str = "/auth/oidc/login";
+pub const OIDC_CALLBACK_PATH: &str = "/auth/oidc/callback";
+// start of every single sign-on username, e.g. `sso:ada@example.com`
+pub const SSO_USERNAME_PREFIX: &str = "sso:";
+
+// the login page only offers sso when it is set up
+#[server(name = SsoEnabledSrv, prefix = "/api", client = crate::app::CsrfClient)]
+pub async fn sso_enabled_srv() -> Result<bool, ServerFnError> {
+  Ok(oidc_config().is_some())
+}
+
+cfg_if::cfg_if! {
+  if #[cfg(feature = "ssr")] {
+    use actix_web::cookie::{time::Duration, Cookie, SameSite};
+    use actix_web::http::header;
+    use actix_web::{get, web, HttpRequest, HttpResponse};
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use jsonwebtoken::jwk::JwkSet;
+    use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+    use once_cell::sync::Lazy;
+    use serde::de::DeserializeOwned;
+    use serde::{Deserialize, Serialize};
+    use serde_json::{Map, Value};
+    use sha2::{Digest, Sha256};
+    use url::Url;
+    use uuid::Uuid;
+
+    use crate::app::auth::{open_session, session_cookie, SECURE_COOKIES};
+    use crate::app::{AuthError, Role, LOGIN_PATH};
+
+    // state, nonce and code verifier travel in this cookie from the redirect to the provider back to the callback
+    const PENDING_LOGIN_COOKIE: &str = "dashboard_oidc";
+    const PENDING_LOGIN_MINUTES: i64 = 10;
+
+    pub struct OidcConfig {
+      // discovery is read from `{issuer}/.well-known/openid-configuration`
+      pub issuer: String,
+      pub client_id: String,
+      // left out for public clients, pkce alone protects the code then
+      pub client_secret: Option<String>,
+      // `OIDC_CALLBACK_PATH` on this server as the provider sees it, must be registered there
+      pub redirect_url: String,
+      pub scopes: String,
+      // id token claim with the user's group names
+      pub groups_claim: String,
+      // group name and the role its members get, members of none of the groups are viewers
+      pub group_roles: Vec<(String, Role)>,
+    }
+
+    impl OidcConfig {
+      // DASHBOARD_OIDC_ISSUER, DASHBOARD_OIDC_CLIENT_ID and DASHBOARD_OIDC_REDIRECT_URL turn sso on
+      // DASHBOARD_OIDC_CLIENT_SECRET, _SCOPES, _GROUPS_CLAIM and _GROUP_ROLES (`hr=hr_admin,leads=manager`) are optional
+      pub fn from_env() -> Option<OidcConfig> {
+        let var = |name: &str| {
+          std::env::var(format!("DASHBOARD_OIDC_{}", name)).ok().filter(|value| !value.trim().is_empty())
+        };
+        Some(OidcConfig {
+          issuer: var("ISSUER")?.trim_end_matches('/').to_string(),
+          client_id: var("CLIENT_ID")?,
+          client_secret: var("CLIENT_SECRET"),
+          redirect_url: var("REDIRECT_URL")?,
+          scopes: var("SCOPES").unwrap_or_else(|| String::from("openid profile email")),
+          groups_claim: var("GROUPS_CLAIM").unwrap_or_else(|| String::from("groups")),
+          group_roles: parse_group_roles(&var("GROUP_ROLES").unwrap_or_default()),
+        })
+      }
+
+      // the highest role any of the groups maps to
+      pub fn role_for_groups(&self, groups: &[String]) -> Role {
+        self.group_roles
+          .iter()
+          .filter(|(group, _)| groups.contains(group))
+          .map(|(_, role)| *role)
+          .max()
+          .unwrap_or_default()
+      }
+    }
+
+    // `group=role` pairs separated by commas, roles by their snake_case name
+    // a pair that can't be read is reported and skipped rather than stopping the server
+    pub fn parse_group_roles(group_roles: &str) -> Vec<(String, Role)> {
+      group_roles
+        .split(',')
+        .filter(|pair| !pair.trim().is_empty())
+        .filter_map(|pair| {
+          let role = pair
+            .split_once('=')
+            .and_then(|(group, role)| Some((group.trim().to_string(), serde_json::from_value(Value::from(role.trim())).ok()?)));
+          if role.is_none() {
+            println!("Error in DASHBOARD_OIDC_GROUP_ROLES, skipping \"{}\"", pair);
+          }
+          role
+        })
+        .collect()
+    }
+
+    static OIDC_CONFIG: Lazy<Option<OidcConfig>> = Lazy::new(OidcConfig::from_env);
+
+    pub fn oidc_config() -> Option<&'static OidcConfig> {
+      OIDC_CONFIG.as_ref()
+    }
+
+    // the parts of the provider's discovery document used here
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct ProviderMetadata {
+      pub issuer: String,
+      pub authorization_endpoint: String,
+      pub token_endpoint: String,
+      pub jwks_uri: String,
+    }
+
+    pub async fn discover(config: &OidcConfig) -> Result<ProviderMetadata, AuthError> {
+      let discovery_url = format!("{}/.well-known/openid-configuration", config.issuer);
+      let metadata: ProviderMetadata = fetch_json(reqwest::Client::new().get(discovery_url)).await?;
+      // tokens are checked against this issuer, so it has to be the configured one
+      if metadata.issuer.trim_end_matches('/') != config.issuer {
+        return Err(sso_failure("discovery document is for another issuer"));
+      }
+      Ok(metadata)
+    }
+
+    async fn fetch_json<T: DeserializeOwned>(request: reqwest::RequestBuilder) -> Result<T, AuthError> {
+      let response = request.send().await.map_err(sso_failure)?;
+      if !response.status().is_success() {
+        return Err(sso_failure(format!("provider answered {}", response.status())));
+      }
+      response.json().await.map_err(sso_failure)
+    }
+
+    fn sso_failure(reason: impl ToString) -> AuthError {
+      AuthError::SsoFailure(reason.to_string())
+    }
+
+    // what has to be remembered between sending the user to the provider and them coming back
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct PendingLogin {
+      pub state: String,
+      pub nonce: String,
+      pub code_verifier: String,
+    }
+
+    impl PendingLogin {
+      pub fn generate() -> PendingLogin {
+        let random = || format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        PendingLogin { state: random(), nonce: random(), code_verifier: random() }
+      }
+
+      pub fn from_request(req: &HttpRequest) -> Option<PendingLogin> {
+        let cookie = req.cookie(PENDING_LOGIN_COOKIE)?;
+        let json = URL_SAFE_NO_PAD.decode(cookie.value()).ok()?;
+        serde_json::from_slice(&json).ok()
+      }
+
+      pub fn cookie(&self) -> Cookie<'static> {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        pending_login_cookie(URL_SAFE_NO_PAD.encode(json))
+      }
+    }
+
+    // only sent back to the sso paths, lax so it comes along on the provider's redirect
+    fn pending_login_cookie(value: String) -> Cookie<'static> {
+      Cookie::build(PENDING_LOGIN_COOKIE, value)
+        .path("/auth/oidc")
+        .http_only(true)
+        .secure(*SECURE_COOKIES)
+        .same_site(SameSite::Lax)
+        .max_age(Duration::minutes(PENDING_LOGIN_MINUTES))
+        .finish()
+    }
+
+    fn expired_pending_login_cookie() -> Cookie<'static> {
+      let mut cookie = pending_login_cookie(String::new());
+      cookie.make_removal();
+      cookie
+    }
+
+    // S256, the provider only hands out tokens for the code to whoever knows the verifier behind this
+    pub fn code_challenge(code_verifier: &str) -> String {
+      URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+    }
+
+    pub fn authorization_url(
+      config: &OidcConfig,
+      metadata: &ProviderMetadata,
+      pending_login: &PendingLogin,
+    ) -> Result<String, AuthError> {
+      let code_challenge = code_challenge(&pending_login.code_verifier);
+      Url::parse_with_params(&metadata.authorization_endpoint, [
+        ("response_type", "code"),
+        ("client_id", config.client_id.as_str()),
+        ("redirect_uri", config.redirect_url.as_str()),
+        ("scope", config.scopes.as_str()),
+        ("state", pending_login.state.as_str()),
+        ("nonce", pending_login.nonce.as_str()),
+        ("code_challenge", code_challenge.as_str()),
+        ("code_challenge_method", "S256"),
+      ])
+      .map(String::from)
+      .map_err(sso_failure)
+    }
+
+    #[derive(Deserialize)]
+    struct TokenResponse {
+      id_token: String,
+    }
+
+    // who logged in according to the verified id token
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct SsoUser {
+      pub username: String,
+      pub role: Role,
+    }
+
+    // swaps the code for tokens and checks the id token, `state` is the one the callback came back with
+    pub async fn finish_login(
+      config: &OidcConfig,
+      metadata: &ProviderMetadata,
+      pending_login: &PendingLogin,
+      code: &str,
+      state: &str,
+    ) -> Result<SsoUser, AuthError> {
+      if state != pending_login.state {
+        return Err(sso_failure("state does not match the login in progress"));
+      }
+
+      let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", config.redirect_url.as_str()),
+        ("client_id", config.client_id.as_str()),
+        ("code_verifier", pending_login.code_verifier.as_str()),
+      ];
+      if let Some(client_secret) = &config.client_secret {
+        form.push(("client_secret", client_secret.as_str()));
+      }
+      let token_response: TokenResponse =
+        fetch_json(reqwest::Client::new().post(&metadata.token_endpoint).form(&form)).await?;
+
+      let claims = verify_id_token(config, metadata, &token_response.id_token).await?;
+      sso_user(config, &claims, &pending_login.nonce)
+    }
+
+    // signature, issuer, audience and expiry
+    // the key comes from the provider's jwks, or is the client secret for hmac signed tokens
+    async fn verify_id_token(
+      config: &OidcConfig,
+      metadata: &ProviderMetadata,
+      id_token: &str,
+    ) -> Result<Map<String, Value>, AuthError> {
+      let token_header = jsonwebtoken::decode_header(id_token).map_err(sso_failure)?;
+      let key = match token_header.alg {
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+          let client_secret = config
+            .client_secret
+            .as_ref()
+            .ok_or_else(|| sso_failure("hmac signed id token but no client secret is set"))?;
+          DecodingKey::from_secret(client_secret.as_bytes())
+        }
+        _ => {
+          let jwks: JwkSet = fetch_json(reqwest::Client::new().get(&metadata.jwks_uri)).await?;
+          let jwk = match &token_header.kid {
+            Some(kid) => jwks.find(kid),
+            None => jwks.keys.first(),
+          }
+          .ok_or_else(|| sso_failure("no signing key for the id token"))?;
+          DecodingKey::from_jwk(jwk).map_err(sso_failure)?
+        }
+      };
+
+      let mut validation = Validation::new(token_header.alg);
+      validation.set_issuer(&[&metadata.issuer]);
+      validation.set_audience(&[&config.client_id]);
+      jsonwebtoken::decode::<Map<String, Value>>(id_token, &key, &validation)
+        .map(|token_data| token_data.claims)
+        .map_err(sso_failure)
+    }
+
+    // the dashboard username is the email if the provider verified it, else the provider's subject id
+    // never the preferred username, users can pick that themselves and take over someone else's
+    // prefixed with `sso:` so it can't match a local account either
+    fn sso_user(config: &OidcConfig, claims: &Map<String, Value>, nonce: &str) -> Result<SsoUser, AuthError> {
+      if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+        return Err(sso_failure("nonce does not match the login in progress"));
+      }
+      let email_verified = claims.get("email_verified").and_then(Value::as_bool) == Some(true);
+      let identity = claims
+        .get("email")
+        .and_then(Value::as_str)
+        .filter(|_| email_verified)
+        .or_else(|| claims.get("sub").and_then(Value::as_str))
+        .ok_or_else(|| sso_failure("id token has no subject"))?;
+      let groups: Vec<String> = match claims.get(&config.groups_claim) {
+        Some(Value::Array(groups)) => groups.iter().filter_map(Value::as_str).map(String::from).collect(),
+        Some(Value::String(group)) => vec![group.clone()],
+        _ => Vec::new(),
+      };
+      Ok(SsoUser { username: format!("{}{}", SSO_USERNAME_PREFIX, identity), role: config.role_for_groups(&groups) })
+    }
+
+    fn sso_failed_redirect() -> HttpResponse {
+      HttpResponse::Found()
+        .insert_header((header::LOCATION, format!("{}?error=sso", LOGIN_PATH)))
+        .cookie(expired_pending_login_cookie())
+        .finish()
+    }
+
+    // the "log in with your company account" link, sends the browser on to the provider
+    #[get("/auth/oidc/login")]
+    pub async fn oidc_login() -> HttpResponse {
+      let Some(config) = oidc_config() else {
+        return sso_failed_redirect();
+      };
+      let pending_login = PendingLogin::generate();
+      let authorization = match discover(config).await {
+        Ok(metadata) => authorization_url(config, &metadata, &pending_login),
+        Err(auth_error) => Err(auth_error),
+      };
+
+      match authorization {
+        Ok(authorization_url) => HttpResponse::Found()
+          .insert_header((header::LOCATION, authorization_url))
+          .cookie(pending_login.cookie())
+          .finish(),
+        Err(auth_error) => {
+          println!("Error in starting sso login: {}", auth_error);
+          sso_failed_redirect()
+        }
+      }
+    }
+
+    #[derive(Deserialize)]
+    pub struct CallbackQuery {
+      code: Option<String>,
+      state: Option<String>,
+      // set instead of the code when the provider turned the login down
+      error: Option<String>,
+    }
+
+    // where the provider sends the browser back to, opens a session like the password login does
+    #[get("/auth/oidc/callback")]
+    pub async fn oidc_callback(req: HttpRequest, query: web::Query<CallbackQuery>) -> HttpResponse {
+      let logged_in = async {
+        let config = oidc_config().ok_or_else(|| sso_failure("sso is not configured"))?;
+        if let Some(error) = &query.error {
+          return Err(sso_failure(format!("provider answered {}", error)));
+        }
+        let pending_login = PendingLogin::from_request(&req).ok_or_else(|| sso_failure("no login in progress"))?;
+        let (Some(code), Some(state)) = (&query.code, &query.state) else {
+          return Err(sso_failure("callback without code or state"));
+        };
+
+        let metadata = discover(config).await?;
+        let sso_user = finish_login(config, &metadata, &pending_login, code, state).await?;
+        open_session(sso_user.username, Some(sso_user.role)).await
+      }
+      .await;
+
+      match logged_in {
+        Ok(token) => HttpResponse::Found()
+          .insert_header((header::LOCATION, "/"))
+          .cookie(session_cookie(&token))
+          .cookie(expired_pending_login_cookie())
+          .finish(),
+        Err(auth_error) => {
+          println!("Error in sso callback: {}", auth_error);
+          sso_failed_redirect()
+        }
+      }
+    }
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use actix_web::{web, App, HttpResponse, HttpServer};
+  use serde_json::json;
+  use std::collections::HashMap;
+  use std::sync::Mutex;
+
+  use super::{
+    authorization_url, code_challenge, discover, finish_login, parse_group_roles, sso_user, OidcConfig, PendingLogin,
+  };
+  use crate::app::Role;
+
+  const CLIENT_SECRET: &str = "mock client secret";
+
+  // codes the mock provider handed out, with the challenge and nonce of their authorization request
+  type IssuedCodes = web::Data<Mutex<HashMap<String, (String, String)>>>;
+
+  async fn mock_discovery(issuer: web::Data<String>) -> HttpResponse {
+    HttpResponse::Ok().json(json!({
+      "issuer": issuer.as_str(),
+      "authorization_endpoint": format!("{}/authorize", issuer.as_str()),
+      "token_endpoint": format!("{}/token", issuer.as_str()),
+      "jwks_uri": format!("{}/jwks", issuer.as_str()),
+    }))
+  }
+
+  // checks the pkce verifier like a real provider and answers with an hmac signed id token
+  async fn mock_token(
+    issuer: web::Data<String>,
+    issued_codes: IssuedCodes,
+    form: web::Form<HashMap<String, String>>,
+  ) -> HttpResponse {
+    let Some((challenge, nonce)) = issued_codes.lock().unwrap().remove(&form["code"]) else {
+      return HttpResponse::BadRequest().json(json!({ "error": "invalid_grant" }));
+    };
+    if code_challenge(&form["code_verifier"]) != challenge || form["client_secret"] != CLIENT_SECRET {
+      return HttpResponse::BadRequest().json(json!({ "error": "invalid_grant" }));
+    }
+    let claims = json!({
+      "iss": issuer.as_str(),
+      "aud": form["client_id"],
+      "sub": "u-1",
+      "preferred_username": "ada",
+      "email": "ada@example.com",
+      "email_verified": true,
+      "groups": ["staff", "hr"],
+      "nonce": nonce,
+      "exp": chrono::Utc::now().timestamp() + 300,
+    });
+    let key = jsonwebtoken::EncodingKey::from_secret(CLIENT_SECRET.as_bytes());
+    let id_token = jsonwebtoken::encode(&jsonwebtoken::Header::default(), &claims, &key).unwrap();
+    HttpResponse::Ok().json(json!({ "access_token": "unused", "token_type": "Bearer", "id_token": id_token }))
+  }
+
+  #[test]
+  fn sso_users_get_the_highest_role_and_a_namespaced_username() {
+    let group_roles = parse_group_roles("leads = manager, hr=hr_admin, oops, staff=owner");
+    assert_eq!(group_roles, vec![("leads".to_string(), Role::Manager), ("hr".to_string(), Role::HrAdmin)]);
+
+    let config = OidcConfig {
+      issuer: String::new(),
+      client_id: String::new(),
+      client_secret: None,
+      redirect_url: String::new(),
+      scopes: String::new(),
+      groups_claim: String::from("groups"),
+      group_roles,
+    };
+    assert_eq!(config.role_for_groups(&["leads".into(), "hr".into()]), Role::HrAdmin);
+    assert_eq!(config.role_for_groups(&["staff".into()]), Role::Viewer);
+
+    // an email the provider didn't verify could be anyone's, and so could the preferred username
+    let claims = json!({ "sub": "u-2", "email": "grace@example.com", "preferred_username": "ada", "nonce": "n" });
+    let sso_user = sso_user(&config, claims.as_object().unwrap(), "n").unwrap();
+    assert_eq!(sso_user.username, "sso:u-2");
+  }
+
+  #[actix_web::test]
+  async fn code_flow_against_a_mock_provider() {
+    let issued_codes: IssuedCodes = web::Data::new(Mutex::new(HashMap::new()));
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let issuer = format!("http://{}", listener.local_addr().unwrap());
+    let server = {
+      let (issuer, issued_codes) = (issuer.clone(), issued_codes.clone());
+      HttpServer::new(move || {
+        App::new()
+          .app_data(web::Data::new(issuer.clone()))
+          .app_data(issued_codes.clone())
+          .route("/.well-known/openid-configuration", web::get().to(mock_discovery))
+          .route("/token", web::post().to(mock_token))
+      })
+      .workers(1)
+      .listen(listener)
+      .unwrap()
+      .run()
+    };
+    actix_web::rt::spawn(server);
+
+    let config = OidcConfig {
+      issuer: issuer.clone(),
+      client_id: String::from("dashboard"),
+      client_secret: Some(String::from(CLIENT_SECRET)),
+      redirect_url: String::from("http://localhost:3000/auth/oidc/callback"),
+      scopes: String::from("openid profile groups"),
+      groups_claim: String::from("groups"),
+      group_roles: parse_group_roles("hr=hr_admin"),
+    };
+    let metadata = discover(&config).await.unwrap();
+    let pending_login = PendingLogin::generate();
+
+    // the user logs in at the provider, which remembers what the authorization request asked for
+    let authorization_url = url::Url::parse(&authorization_url(&config, &metadata, &pending_login).unwrap()).unwrap();
+    let params: HashMap<String, String> = authorization_url.query_pairs().into_owned().collect();
+    assert_eq!(params["code_challenge_method"], "S256");
+    assert_eq!(params["state"], pending_login.state);
+    let issue_code = |code: &str| {
+      issued_codes.lock().unwrap().insert(code.into(), (params["code_challenge"].clone(), params["nonce"].clone()));
+    };
+
+    issue_code("code-1");
+    let sso_user = finish_login(&config, &metadata, &pending_login, "code-1", &pending_login.state).await.unwrap();
+    assert_eq!((sso_user.username.as_str(), sso_user.role), ("sso:ada@example.com", Role::HrAdmin));
+
+    // a code used with a different verifier, or a callback for another login, gets nowhere
+    issue_code("code-2");
+    let other_login = PendingLogin { state: pending_login.state.clone(), ..PendingLogin::generate() };
+    assert!(finish_login(&config, &metadata, &other_login, "code-2", &other_login.state).await.is_err());
+    issue_code("code-3");
+    assert!(finish_login(&config, &metadata, &pending_login, "code-3", "forged state").await.is_err());
+  }
+}
@@ -0,0 +1,182 @@
+//openapi document for the /api/v1 json api, built from the request/response types and handler annotations
+cfg_if::cfg_if! {
+  if #[cfg(feature = "ssr")] {
+
+    use actix_web::{get, HttpResponse};
+    use utoipa::openapi::path::PathItemType;
+    use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+    use utoipa::openapi::{OpenApi as OpenApiDocument, RefOr};
+    use utoipa::{Modify, OpenApi};
+
+    use crate::app::rest_api::{self, ApiErrorBody};
+    use crate::app::{AddPersonRequest, DeletePersonRequest, EditPersonRequest, PatchPersonRequest, Person};
+
+    pub const OPENAPI_PATH: &str = "/api/openapi.json";
+
+    #[derive(OpenApi)]
+    #[openapi(
+      info(title = "Dashboard App API", description = "Team members of the dashboard"),
+      paths(
+        rest_api::list_persons,
+        rest_api::create_person,
+        rest_api::show_person,
+        rest_api::patch_person,
+        rest_api::remove_person,
+      ),
+      components(schemas(
+        Person,
+        AddPersonRequest,
+        EditPersonRequest,
+        PatchPersonRequest,
+        DeletePersonRequest,
+        ApiErrorBody,
+      )),
+      tags((name = "persons", description = "team members")),
+      modifiers(&ApiTokenAuth),
+      security(("api_token" = [])),
+    )]
+    pub struct ApiDoc;
+
+    // scripts send an api token, the browser's session cookie works too
+    struct ApiTokenAuth;
+
+    impl Modify for ApiTokenAuth {
+      fn modify(&self, openapi: &mut OpenApiDocument) {
+        if let Some(components) = openapi.components.as_mut() {
+          components.add_security_scheme(
+            "api_token",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+          );
+        }
+      }
+    }
+
+    #[get("/api/openapi.json")]
+    pub async fn openapi_json() -> HttpResponse {
+      HttpResponse::Ok().json(ApiDoc::openapi())
+    }
+
+    // plain html rendered from the same document, nothing is loaded from outside the server
+    #[get("/api/docs")]
+    pub async fn api_docs() -> HttpResponse {
+      HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(render_docs(&ApiDoc::openapi()))
+    }
+
+    pub fn method_name(path_item_type: &PathItemType) -> &'static str {
+      match path_item_type {
+        PathItemType::Get => "GET",
+        PathItemType::Post => "POST",
+        PathItemType::Put => "PUT",
+        PathItemType::Delete => "DELETE",
+        PathItemType::Options => "OPTIONS",
+        PathItemType::Head => "HEAD",
+        PathItemType::Patch => "PATCH",
+        PathItemType::Trace => "TRACE",
+        PathItemType::Connect => "CONNECT",
+      }
+    }
+
+    pub fn escape_html(text: &str) -> String {
+      text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+    }
+
+    fn render_docs(document: &OpenApiDocument) -> String {
+      let mut operations = String::new();
+      for (path, path_item) in document.paths.paths.iter() {
+        for (path_item_type, operation) in path_item.operations.iter() {
+          let mut responses = String::new();
+          for (status, response) in operation.responses.responses.iter() {
+            let description = match response {
+              RefOr::T(response) => response.description.clone(),
+              RefOr::Ref(reference) => reference.ref_location.clone(),
+            };
+            responses.push_str(&format!(
+              "<li><code>{}</code> {}</li>",
+              escape_html(status),
+              escape_html(&description)
+            ));
+          }
+          operations.push_str(&format!(
+            "<section><h3><code>{} {}</code></h3><ul>{}</ul></section>",
+            method_name(path_item_type),
+            escape_html(path),
+            responses
+          ));
+        }
+      }
+
+      let schemas = document
+        .components
+        .as_ref()
+        .map(|components| serde_json::to_string_pretty(&components.schemas).unwrap_or_default())
+        .unwrap_or_default();
+
+      format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{title}</title>\
+        <style>body{{font-family:sans-serif;max-width:52rem;margin:2rem auto;padding:0 1rem}}\
+        pre{{background:#f4f4f4;padding:1rem;overflow-x:auto}}</style></head>\
+        <body><h1>{title} {version}</h1><p>Machine readable spec: <a href=\"{openapi_path}\">{openapi_path}</a></p>\
+        <h2>Operations</h2>{operations}<h2>Schemas</h2><pre>{schemas}</pre></body></html>",
+        title = escape_html(&document.info.title),
+        version = escape_html(&document.info.version),
+        openapi_path = OPENAPI_PATH,
+        operations = operations,
+        schemas = escape_html(&schemas),
+      )
+    }
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use actix_web::http::{header, Method};
+  use actix_web::{test, web, App, HttpMessage};
+  use utoipa::OpenApi;
+
+  use super::{method_name, ApiDoc};
+  use crate::app::rest_api::{self, API_V1_PATH};
+  use crate::app::{CurrentUser, Role};
+
+  // every operation in the spec must reach a handler, and whatever it answers must be documented
+  // runs without a database: handlers that need one fail the way the spec says they can
+  #[actix_web::test]
+  async fn spec_matches_routed_handlers() {
+    let app = test::init_service(App::new().service(web::scope(API_V1_PATH).configure(rest_api::configure))).await;
+
+    for (path, path_item) in ApiDoc::openapi().paths.paths.iter() {
+      for (path_item_type, operation) in path_item.operations.iter() {
+        let method = method_name(path_item_type);
+        // a body that can never deserialize keeps the handlers from writing anything
+        let req = test::TestRequest::default()
+          .method(Method::from_bytes(method.as_bytes()).unwrap())
+          .uri(&path.replace("{uuid}", "spec-drift-check"))
+          .insert_header((header::CONTENT_TYPE, "application/json"))
+          .set_payload("not json")
+          .to_request();
+        // what `require_session` leaves for a logged in hr admin, so no role check stops the request early
+        req.extensions_mut().insert(CurrentUser { username: "hr".into(), role: Role::HrAdmin, token_scope: None });
+        let res = test::call_service(&app, req).await;
+        let status = res.status();
+
+        assert!(
+          operation.responses.responses.contains_key(status.as_str()),
+          "{} {} answered {} which the spec does not document",
+          method,
+          path,
+          status
+        );
+        if !status.is_success() {
+          // a missing route would also be a 404/405, but without our error body
+          let body: serde_json::Value = test::read_body_json(res).await;
+          assert!(body["error"].is_string(), "{} {} is not routed to a handler", method, path);
+        }
+      }
+    }
+  }
+}
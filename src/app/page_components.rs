@@ -1,38 +1,70 @@
 use leptos::*;
-use crate::app::{Header, DashboardHeader, AddPersonModal, Toast, ToastMessage, get_persons_srv, PersonRow, DashboardChart};
+use crate::app::{BulkActionsBar, PersonStore, Header, DashboardHeader, AddPersonModal, Toast, ToastMessage, ToastMessageType, PersonRow, DashboardChart, PersonDetails, ImportPersonsModal, ExportPersonsModal, use_person_store, use_current_user, is_modal_open, modal_href, get_person_srv, get_dashboard_stats_srv, get_compensation_stats_srv, get_team_history_srv, CompensationCharts, TeamHistoryChart, PeriodPicker, ReportLinks, use_period, DEFAULT_HISTORY_RANGE, Person, ADD_MODAL, IMPORT_MODAL, EXPORT_MODAL, TEAM_PATH, TITLE_FILTER};
+use super::NotFound;
+use leptos_meta::*;
+use leptos_router::*;
 use std::rc::Rc;
+use crate::app::history::last_months;
 
 #[component]
 pub fn HomePage() -> impl IntoView {
-  let person_info_rsc = create_resource(
-    || (), 
-    move |_| async move { get_persons_srv().await},
+  // every figure is for the team as it stood at the end of the picked period
+  let period = use_period();
+  let dashboard_stats = create_resource(
+    move || period.get().as_of(),
+    |as_of| async move { get_dashboard_stats_srv(as_of.to_string()).await },
+  );
+  let compensation_stats = create_resource(
+    move || period.get().as_of(),
+    |as_of| async move { get_compensation_stats_srv(as_of.to_string()).await },
+  );
+  // months shown by the history chart, up to the last one of the period
+  let history_range = create_rw_signal(DEFAULT_HISTORY_RANGE);
+  let team_history = create_resource(
+    move || (history_range.get(), period.get().to),
+    |(months, last_month)| async move {
+      let (from, to) = last_months(months, last_month);
+      get_team_history_srv(from, to).await
+    },
   );
   view! {
     <body class="bg-gray-900 overflow-x-hidden">
       <div class="w-full max-w-[64rem] mx-auto items-center justify-center align-middle text-white">
         <Header />
-        <DashboardHeader/>
+        <DashboardHeader period/>
+        <PeriodPicker period/>
+        <ReportLinks/>
         <Suspense fallback= move || {
           view! { <p> Loading ....</p>}
         }> 
           {
             move || {
-              person_info_rsc.get().map(|data| {
+              dashboard_stats.get().map(|data| {
                 match data {
-                  Ok(persons_data) => {
+                  Ok(stats) => {
+                    let deltas = team_history.get().and_then(Result::ok).and_then(|history| history.month_over_month());
                     view! {
-                      <DashboardChart  persons_data/>
+                      <DashboardChart stats deltas/>
                     }.into_view()
                   },
                   Err(_) => view! {
-                    <div>"error loading persons"</div>
+                    <div>"error loading the team figures"</div>
                   }.into_view()
                 }
               })
             }
           }
         </Suspense>
+        <Suspense fallback=|| ()>
+          {move || team_history.get().and_then(Result::ok).map(|history| view! {
+            <TeamHistoryChart history range=history_range/>
+          })}
+        </Suspense>
+        <Suspense fallback=|| ()>
+          {move || compensation_stats.get().and_then(Result::ok).map(|stats| view! {
+            <CompensationCharts stats/>
+          })}
+        </Suspense>
       </div>
     </body>
   }
@@ -41,18 +73,50 @@ pub fn HomePage() -> impl IntoView {
 #[component]
 pub fn TeamPage() -> impl IntoView {
   const ADD_BUTTON_STYLE: &str = "bg-[#7734e7] px-8 py-2 rounded text-white transition-all duration-1000 ease-in-out hover:bg-[#8448e9]";
+  const IMPORT_BUTTON_STYLE: &str = "bg-[#555555] px-6 py-2 mr-3 rounded text-white transition-all duration-1000 ease-in-out hover:bg-[#666666]";
 
-  let (if_show_modal, set_if_show_modal) = create_signal(false);
-  let (if_show_toast, set_if_show_toast) = create_signal(false);
-  let (toast_message, set_toast_message) = create_signal(ToastMessage::new());
-  //create the resource here
-  let get_person_rsc = create_resource(
-    || (),
-    move |_| async move { get_persons_srv().await }  
-  );
-
-  let on_click = move |_| {
-    set_if_show_modal.set(!if_show_modal.get());
+  let if_show_modal = is_modal_open(ADD_MODAL, None);
+  let if_show_import = is_modal_open(IMPORT_MODAL, None);
+  let if_show_export = is_modal_open(EXPORT_MODAL, None);
+  // a plain form post lands back here with `?flash=..`, start with its toast showing
+  let flash = use_query_map().with_untracked(|query| {
+    query.get("flash").and_then(|flash_key| ToastMessageType::from_flash_key(flash_key))
+  });
+  let (if_show_toast, set_if_show_toast) = create_signal(flash.is_some());
+  let (toast_message, set_toast_message) = create_signal(flash.map(ToastMessage::create).unwrap_or_default());
+  let person_store = use_person_store();
+  // `?title=..` from a dashboard chart bar, only that title's members are listed
+  let query = use_query_map();
+  let title_filter = create_memo(move |_| {
+    query.with(|query| query.get(TITLE_FILTER).cloned().filter(|title| !title.is_empty()))
+  });
+  let is_listed = move |person: &Person| title_filter.with(|title_filter| {
+    title_filter.as_ref().is_none_or(|title| &person.title == title)
+  });
+  let current_user = use_current_user();
+  let is_hr_admin = move || current_user.with(|current_user| {
+    current_user.as_ref().is_some_and(|current_user| current_user.can_add_and_delete())
+  });
+  // uuids ticked for a bulk change, only members the user manages can be picked
+  let selected = create_rw_signal(Vec::<String>::new());
+  let selectable_uuids = move || {
+    person_store.persons().and_then(Result::ok).unwrap_or_default()
+      .into_iter()
+      .filter(|person| !PersonStore::is_pending(person))
+      .filter(is_listed)
+      .filter(|person| current_user.with(|current_user| {
+        current_user.as_ref().is_some_and(|current_user| current_user.can_edit(person))
+      }))
+      .map(|person| person.uuid)
+      .collect::<Vec<String>>()
+  };
+  let all_selected = move || {
+    let uuids = selectable_uuids();
+    !uuids.is_empty() && selected.with(|selected| uuids.iter().all(|uuid| selected.contains(uuid)))
+  };
+  let on_select_all = move |event| {
+    let uuids = if event_target_checked(&event) { selectable_uuids() } else { Vec::new() };
+    selected.set(uuids);
   };
 
   view! {
@@ -69,19 +133,52 @@ pub fn TeamPage() -> impl IntoView {
             <Show when= move || {
               if_show_modal.get()
             }>
-              <AddPersonModal set_if_show_modal
+              <AddPersonModal
                 set_if_show_added=set_if_show_toast
                 set_toast_message
               />
             </Show>
+            <Show when=move || if_show_import.get()>
+              <ImportPersonsModal
+                set_if_show_toast
+                set_toast_message
+              />
+            </Show>
+            <Show when=move || if_show_export.get()>
+              <ExportPersonsModal/>
+            </Show>
             <div class="flex flex-row w-full max-w-[52rem]">
+              <input type="checkbox" class="mr-4 mt-5 w-4 h-4" aria-label="select all members"
+                prop:checked=all_selected on:change=on_select_all/>
               <div class="pr-4 mt-4 text-xl">"Members"</div>
               <hr class="w-full max-w-[48rem] pl-4 pr-4 pt-4 mt-8 mr-4" />
-              <button
-                class=ADD_BUTTON_STYLE
-                on:click=on_click
-              >"Add"</button>
+              <A
+                href=modal_href(EXPORT_MODAL, None)
+                class=IMPORT_BUTTON_STYLE
+              >"Export"</A>
+              // adding members is left to hr admins
+              <Transition fallback=|| ()>
+                <Show when=is_hr_admin>
+                  <A
+                    href=modal_href(IMPORT_MODAL, None)
+                    class=IMPORT_BUTTON_STYLE
+                  >"Import"</A>
+                  <A
+                    href=modal_href(ADD_MODAL, None)
+                    class=ADD_BUTTON_STYLE
+                  >"Add"</A>
+                </Show>
+              </Transition>
             </div>
+            {move || title_filter.get().map(|title| view! {
+              <div class="flex flex-row w-full max-w-[52rem] mt-4 text-stone-400">
+                <p>{format!("Showing {} only", title)}</p>
+                <A href=TEAM_PATH class="ml-3 text-white hover:text-[#8448e9]">"Show all"</A>
+              </div>
+            })}
+            <Show when=move || selected.with(|selected| !selected.is_empty())>
+              <BulkActionsBar selected set_if_show_toast set_toast_message/>
+            </Show>
             <Suspense fallback= move || {
               view! {<p>"loading ... "</p>}
             }>
@@ -89,15 +186,15 @@ pub fn TeamPage() -> impl IntoView {
               >
               {
                 move || {
-                  get_person_rsc.get().map(|data| {
+                  person_store.persons().map(|data| {
                     match data {
                       Ok(person_data) => { 
-                        person_data.iter().map(|each_person| view! {
+                        person_data.iter().filter(|each_person| is_listed(each_person)).map(|each_person| view! {
                           <PersonRow 
                             person=Rc::new(each_person.clone())
-                            person_resource= get_person_rsc
                             set_if_show_toast
                             set_toast_message
+                            selected
                           />
                         }).collect_view()
                       },
@@ -119,3 +216,45 @@ pub fn TeamPage() -> impl IntoView {
   }
 }
 
+
+#[component]
+pub fn PersonPage() -> impl IntoView {
+  let params = use_params_map();
+  let person_rsc = create_resource(
+    move || params.with(|params| params.get("uuid").cloned().unwrap_or_default()),
+    move |uuid| async move { get_person_srv(uuid).await },
+  );
+
+  view! {
+    <body class="bg-gray-900 overflow-x-hidden relative">
+      <div class="w-full max-w-[64rem] mx-auto items-center justify-center align-middle text-white">
+        <Header />
+        <div class="mt-28">
+          <Suspense fallback= move || {
+            view! {<p>"loading ... "</p>}
+          }>
+            {
+              move || {
+                person_rsc.get().map(|data| {
+                  match data {
+                    Ok(Some(person)) => view! {
+                      <Title text=format!("{} | Team", person.name)/>
+                      <PersonDetails person=Rc::new(person)/>
+                    }.into_view(),
+                    // the route renders in async mode, so this still sets the 404 status
+                    Ok(None) => view! {
+                      <NotFound/>
+                    }.into_view(),
+                    Err(_) => view! {
+                      <div>"error loading person"</div>
+                    }.into_view()
+                  }
+                })
+              }
+            }
+          </Suspense>
+        </div>
+      </div>
+    </body>
+  }
+}
@@ -0,0 +1,197 @@
+//the period the dashboard reports on, kept in the url so a report can be linked to
+//`?period=quarter&from=2024-07` is the quarter holding july, a custom range also has `to`
+use chrono::{Datelike, Months, NaiveDate};
+use leptos::*;
+use leptos_router::*;
+
+use crate::app::history::{last_day_of_month, month_key, months_between, parse_month};
+
+pub const PERIOD_PARAM: &str = "period";
+pub const FROM_PARAM: &str = "from";
+pub const TO_PARAM: &str = "to";
+
+const PICKER_STYLE: &str = "w-full max-w-[53rem] mx-auto flex flex-row flex-wrap items-center gap-3 px-2 mt-6 text-white";
+const PICKER_INPUT_STYLE: &str = "h-10 bg-[#333333] px-4 text-white outline-none";
+const PICKER_BUTTON_STYLE: &str = "bg-[#7734e7] px-6 py-2 rounded text-white transition-all duration-1000 ease-in-out hover:bg-[#8448e9]";
+const PICKER_LINK_STYLE: &str = "text-stone-400 hover:text-white";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PeriodKind {
+  #[default]
+  Month,
+  Quarter,
+  Year,
+  Custom,
+}
+
+impl PeriodKind {
+  pub const ALL: [PeriodKind; 4] = [PeriodKind::Month, PeriodKind::Quarter, PeriodKind::Year, PeriodKind::Custom];
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      PeriodKind::Month => "month",
+      PeriodKind::Quarter => "quarter",
+      PeriodKind::Year => "year",
+      PeriodKind::Custom => "custom",
+    }
+  }
+
+  pub fn parse(kind: &str) -> Option<PeriodKind> {
+    PeriodKind::ALL.into_iter().find(|period_kind| period_kind.as_str() == kind)
+  }
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      PeriodKind::Month => "Month",
+      PeriodKind::Quarter => "Quarter",
+      PeriodKind::Year => "Year",
+      PeriodKind::Custom => "Custom range",
+    }
+  }
+}
+
+// `from` and `to` are the first days of the first and the last month
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+  pub kind: PeriodKind,
+  pub from: NaiveDate,
+  pub to: NaiveDate,
+}
+
+impl Period {
+  // the month, quarter or year holding `day`, a custom range is just its month
+  pub fn containing(kind: PeriodKind, day: NaiveDate) -> Period {
+    let month = day.with_day(1).unwrap_or(day);
+    let (from, months) = match kind {
+      PeriodKind::Month | PeriodKind::Custom => (month, 1),
+      PeriodKind::Quarter => (month.with_month0(month.month0() / 3 * 3).unwrap_or(month), 3),
+      PeriodKind::Year => (month.with_month0(0).unwrap_or(month), 12),
+    };
+    // cut short only in the last year chrono can hold
+    let to = from.checked_add_months(Months::new(months - 1)).unwrap_or(from);
+    Period { kind, from, to }
+  }
+
+  // the period of the dashboard url, the current month when it has none or it doesn't parse
+  pub fn from_query(query: &ParamsMap, today: NaiveDate) -> Period {
+    let kind = query.get(PERIOD_PARAM).and_then(|kind| PeriodKind::parse(kind)).unwrap_or_default();
+    let Some(from) = query.get(FROM_PARAM).and_then(|from| parse_month(from)) else {
+      return Period::containing(kind, today);
+    };
+    match (kind, query.get(TO_PARAM).and_then(|to| parse_month(to))) {
+      (PeriodKind::Custom, Some(to)) => Period { kind, from: from.min(to), to: from.max(to) },
+      _ => Period::containing(kind, from),
+    }
+  }
+
+  // `to` is never before `from`, so this is at least 1
+  pub fn months(&self) -> u32 {
+    months_between(self.from, self.to).unwrap_or(1)
+  }
+
+  // the last day of the period, the figures are the team as it stood then
+  pub fn as_of(&self) -> NaiveDate {
+    last_day_of_month(self.to)
+  }
+
+  // the period of the same kind and length right before or after this one, or this one at the end of the calendar
+  pub fn shifted(&self, forward: bool) -> Period {
+    let months = Months::new(self.months());
+    let shifted = match forward {
+      true => (self.from.checked_add_months(months), self.to.checked_add_months(months)),
+      false => (self.from.checked_sub_months(months), self.to.checked_sub_months(months)),
+    };
+    match shifted {
+      (Some(from), Some(to)) => Period { kind: self.kind, from, to },
+      _ => *self,
+    }
+  }
+
+  // e.g. "September 2024", "Q3 2024" or "Jul 2024 - Sep 2024"
+  pub fn label(&self) -> String {
+    match self.kind {
+      PeriodKind::Month => self.from.format("%B %Y").to_string(),
+      PeriodKind::Quarter => format!("Q{} {}", self.from.month0() / 3 + 1, self.from.year()),
+      PeriodKind::Year => self.from.year().to_string(),
+      PeriodKind::Custom if self.from == self.to => self.from.format("%B %Y").to_string(),
+      PeriodKind::Custom => format!("{} - {}", self.from.format("%b %Y"), self.to.format("%b %Y")),
+    }
+  }
+
+  // the query string of the period, `?period=..&from=..`
+  pub fn query(&self) -> String {
+    let mut query = format!("?{}={}&{}={}", PERIOD_PARAM, self.kind.as_str(), FROM_PARAM, month_key(self.from));
+    if self.kind == PeriodKind::Custom {
+      query.push_str(&format!("&{}={}", TO_PARAM, month_key(self.to)));
+    }
+    query
+  }
+}
+
+// the period of the current url, the dashboard and its charts follow it
+pub fn use_period() -> Memo<Period> {
+  let query = use_query_map();
+  create_memo(move |_| query.with(|query| Period::from_query(query, chrono::Local::now().date_naive())))
+}
+
+// a get form, so picking a period is a plain navigation that also works before the wasm has loaded
+// `to` only counts for a custom range
+#[component]
+pub fn PeriodPicker(period: Memo<Period>) -> impl IntoView {
+  view! {
+    <Form method="GET" action="" class=PICKER_STYLE>
+      <select name=PERIOD_PARAM class=PICKER_INPUT_STYLE aria-label="period">
+        {PeriodKind::ALL.into_iter().map(|kind| view! {
+          <option value=kind.as_str() selected=move || period.get().kind == kind>{kind.label()}</option>
+        }).collect_view()}
+      </select>
+      <input type="month" name=FROM_PARAM aria-label="from" class=PICKER_INPUT_STYLE
+        prop:value=move || month_key(period.get().from)/>
+      <input type="month" name=TO_PARAM aria-label="to, for a custom range" class=PICKER_INPUT_STYLE
+        prop:value=move || month_key(period.get().to)/>
+      <button type="submit" class=PICKER_BUTTON_STYLE>"Show"</button>
+      <A href=move || period.get().shifted(false).query() class=PICKER_LINK_STYLE>"Previous"</A>
+      <A href=move || period.get().shifted(true).query() class=PICKER_LINK_STYLE>"Next"</A>
+    </Form>
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use chrono::NaiveDate;
+  use leptos_router::ParamsMap;
+
+  use super::{Period, PeriodKind};
+
+  fn query(pairs: &[(&str, &str)]) -> ParamsMap {
+    let mut query = ParamsMap::new();
+    for (key, value) in pairs {
+      query.insert(key.to_string(), value.to_string());
+    }
+    query
+  }
+
+  #[test]
+  fn periods_come_from_the_url() {
+    let today = NaiveDate::from_ymd_opt(2024, 11, 14).unwrap();
+    let quarter = Period::from_query(&query(&[("period", "quarter"), ("from", "2024-08")]), today);
+    assert_eq!(quarter.label(), "Q3 2024");
+    assert_eq!(quarter.as_of(), NaiveDate::from_ymd_opt(2024, 9, 30).unwrap());
+    assert_eq!(quarter.query(), "?period=quarter&from=2024-07");
+    assert_eq!(quarter.shifted(false).label(), "Q2 2024");
+
+    let custom = Period::from_query(&query(&[("period", "custom"), ("from", "2024-05"), ("to", "2024-02")]), today);
+    assert_eq!((custom.months(), custom.label().as_str()), (4, "Feb 2024 - May 2024"));
+
+    let fallback = Period::from_query(&query(&[("period", "week"), ("from", "soon")]), today);
+    assert_eq!(fallback, Period::containing(PeriodKind::Month, today));
+    assert_eq!(fallback.label(), "November 2024");
+
+    // years chrono can barely hold fall back too, rather than overflow the month arithmetic
+    let far_future = Period::from_query(&query(&[("period", "month"), ("from", "+262142-12")]), today);
+    assert_eq!(far_future, Period::containing(PeriodKind::Month, today));
+    let last_year = Period::containing(PeriodKind::Year, NaiveDate::MAX);
+    assert_eq!(last_year.shifted(true), last_year);
+    assert_eq!(last_year.as_of(), NaiveDate::MAX);
+  }
+}
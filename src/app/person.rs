@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use validator::Validate;
 
 #[derive(Debug, Validate, Deserialize,Serialize, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema, async_graphql::SimpleObject))]
 pub struct Person {
   pub uuid: String,
   #[validate(length(min = 1, message = "name is required"))]
@@ -10,9 +11,13 @@ pub struct Person {
   pub title: String,
   #[validate(length(min = 1, message = "level is required"))]
   pub level: String,
-  #[validate(range(min = 2000, max = 99999))]
-  pub compensation: i32,
+  // none for members whose pay the caller may not see, stored members always have it
+  #[validate(range(min = 2000, max = 99999, message = "compensation must be between 2000 and 99999"))]
+  pub compensation: Option<i32>,
   pub joined_date: String,
+  // username of the manager this member reports to
+  #[serde(default)]
+  pub manager: Option<String>,
 }
 
 impl Person {
@@ -29,13 +34,46 @@ impl Person {
       name,
       title,
       level,
-      compensation,
+      compensation: Some(compensation),
       joined_date,
+      manager: None,
     }
   }
+
+  // an empty name, e.g. from a cleared form input, means no manager
+  pub fn with_manager(self, manager: Option<String>) -> Person {
+    Person { manager: manager.filter(|manager| !manager.trim().is_empty()), ..self }
+  }
+
+  pub fn is_compensation_redacted(&self) -> bool {
+    self.compensation.is_none()
+  }
+}
+
+// the member the tests of every module build on, with the lowercased name as uuid and no joined date
+// other fields can be set with struct update syntax, e.g. `compensation: None` for hidden pay
+#[cfg(test)]
+impl Person {
+  pub fn test_member(name: &str, title: &str, level: &str, compensation: i32) -> Person {
+    Person::new(name.to_lowercase(), name.into(), title.into(), level.into(), compensation, String::new())
+  }
+}
+
+// a removed member, kept with the day they left so reports on past months still count them
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone)]
+pub struct Departure {
+  pub person: Person,
+  pub left_date: String,
+}
+
+impl Departure {
+  pub fn new(person: Person, left_date: String) -> Departure {
+    Departure { person, left_date }
+  }
 }
 
 #[derive(Debug, Validate, Deserialize, Serialize, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema, async_graphql::InputObject))]
 pub struct AddPersonRequest {
   #[validate(length(min = 1, message = "name is required"))]
   pub name: String,
@@ -43,8 +81,10 @@ pub struct AddPersonRequest {
   pub title: String,
   #[validate(length(min = 1, message = "level is required"))]
   pub level: String,  
-  #[validate(range(min = 2000, max = 99999))]
+  #[validate(range(min = 2000, max = 99999, message = "compensation must be between 2000 and 99999"))]
   pub compensation: i32,
+  #[serde(default)]
+  pub manager: Option<String>,
 }
 
 impl AddPersonRequest {
@@ -59,11 +99,17 @@ impl AddPersonRequest {
       title,
       level,
       compensation,
+      manager: None,
     }
   }
+
+  pub fn with_manager(self, manager: Option<String>) -> AddPersonRequest {
+    AddPersonRequest { manager, ..self }
+  }
 } 
 
 #[derive(Debug, Validate, Deserialize, Serialize, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema, async_graphql::InputObject))]
 pub struct EditPersonRequest {
   #[validate(length(min = 1, message = "id is required"))]
   pub uuid: String,
@@ -71,8 +117,10 @@ pub struct EditPersonRequest {
   pub title: String,
   #[validate(length(min = 1, message = "level is required"))]
   pub level: String,
-  #[validate(range(min = 2000, max = 99999))]
+  #[validate(range(min = 2000, max = 99999, message = "compensation must be between 2000 and 99999"))]
   pub compensation: i32,
+  #[serde(default)]
+  pub manager: Option<String>,
 }
 
 impl EditPersonRequest {
@@ -82,11 +130,41 @@ impl EditPersonRequest {
       title,
       level,
       compensation,
+      manager: None,
     }
   }
+
+  pub fn with_manager(self, manager: Option<String>) -> EditPersonRequest {
+    EditPersonRequest { manager, ..self }
+  }
 } 
 
+// partial update of a member, fields left out keep their current value
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema))]
+pub struct PatchPersonRequest {
+  pub title: Option<String>,
+  pub level: Option<String>,
+  pub compensation: Option<i32>,
+  pub manager: Option<String>,
+}
+
+impl PatchPersonRequest {
+  // the full edit request for `person` with this patch applied, validate it before saving
+  pub fn apply_to(self, person: &Person) -> EditPersonRequest {
+    EditPersonRequest::new(
+      person.uuid.clone(),
+      self.title.unwrap_or_else(|| person.title.clone()),
+      self.level.unwrap_or_else(|| person.level.clone()),
+      // a stored member always has pay, without it the request fails its range check
+      self.compensation.or(person.compensation).unwrap_or_default(),
+    )
+    .with_manager(self.manager.or_else(|| person.manager.clone()))
+  }
+}
+
 #[derive(Debug, Validate, Deserialize, Serialize, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "ssr", derive(utoipa::ToSchema, async_graphql::InputObject))]
 pub struct DeletePersonRequest {
   #[validate(length(min = 1, message = "id is required"))]
   pub uuid: String,
@@ -0,0 +1,210 @@
+//limits on how often and how much a client can send, so a runaway script can't flood the person table
+//every /api call counts against the caller's ip and, once logged in, against the user or api token
+cfg_if::cfg_if! {
+  if #[cfg(feature = "ssr")] {
+    use std::collections::HashMap;
+    use std::pin::Pin;
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    use actix_web::body::EitherBody;
+    use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+    use actix_web::error::PayloadError;
+    use actix_web::http::header;
+    use actix_web::middleware::Next;
+    use actix_web::web::{self, Bytes};
+    use actix_web::{HttpMessage, HttpResponse};
+    use futures::{Stream, StreamExt};
+
+    use crate::app::rest_api::ApiErrorBody;
+    use crate::app::{CurrentUser, LimitError};
+
+    // a fixed window per key: up to `limit` requests, then wait for the window to end
+    pub struct RateLimiter {
+      limit: u32,
+      window: Duration,
+      counters: Mutex<HashMap<String, (Instant, u32)>>,
+    }
+
+    impl RateLimiter {
+      pub fn new(limit: u32, window: Duration) -> RateLimiter {
+        RateLimiter { limit, window, counters: Mutex::new(HashMap::new()) }
+      }
+
+      // counts the request, or how long until the key may send again
+      pub fn check(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let mut counters = self.counters.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        // ended windows are dropped once in a while so one-off callers don't pile up
+        if counters.len() > 10_000 {
+          counters.retain(|_, (started, _)| now.duration_since(*started) < self.window);
+        }
+
+        let (started, count) = counters.entry(key.to_string()).or_insert((now, 0));
+        if now.duration_since(*started) >= self.window {
+          *started = now;
+          *count = 0;
+        }
+        if *count >= self.limit {
+          return Err(self.window.saturating_sub(now.duration_since(*started)));
+        }
+        *count += 1;
+        Ok(())
+      }
+    }
+
+    pub struct RateLimits {
+      pub per_ip: RateLimiter,
+      pub per_user: RateLimiter,
+      // largest request body accepted, in bytes
+      pub max_body_bytes: usize,
+      // take the client ip from X-Forwarded-For / Forwarded, only behind a proxy that sets them
+      pub trust_forwarded_for: bool,
+    }
+
+    impl RateLimits {
+      // DASHBOARD_RATE_LIMIT_PER_IP and _PER_USER are requests per DASHBOARD_RATE_LIMIT_WINDOW_SECS,
+      // DASHBOARD_MAX_BODY_BYTES the body limit and DASHBOARD_TRUST_FORWARDED_FOR=true reads the proxy headers
+      pub fn from_env() -> RateLimits {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+          match std::env::var(name) {
+            Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+              println!("Error in {}, using the default", name);
+              default
+            }),
+            Err(_) => default,
+          }
+        }
+        let window = Duration::from_secs(var("DASHBOARD_RATE_LIMIT_WINDOW_SECS", 60));
+        RateLimits {
+          per_ip: RateLimiter::new(var("DASHBOARD_RATE_LIMIT_PER_IP", 300), window),
+          per_user: RateLimiter::new(var("DASHBOARD_RATE_LIMIT_PER_USER", 120), window),
+          max_body_bytes: var("DASHBOARD_MAX_BODY_BYTES", 2 * 1024 * 1024),
+          trust_forwarded_for: var("DASHBOARD_TRUST_FORWARDED_FOR", false),
+        }
+      }
+    }
+
+    fn client_ip(req: &ServiceRequest, trust_forwarded_for: bool) -> String {
+      let connection_info = req.connection_info();
+      let ip = match trust_forwarded_for {
+        true => connection_info.realip_remote_addr(),
+        false => connection_info.peer_addr(),
+      };
+      ip.unwrap_or("unknown").to_string()
+    }
+
+    fn too_many_requests(retry_after: Duration) -> HttpResponse {
+      // rounded up, a client waiting the whole seconds given is let through
+      let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+      HttpResponse::TooManyRequests()
+        .insert_header((header::RETRY_AFTER, seconds.to_string()))
+        .json(ApiErrorBody { error: LimitError::TooManyRequests(seconds).to_string(), fields: None })
+    }
+
+    fn payload_too_large(max_body_bytes: usize) -> HttpResponse {
+      HttpResponse::PayloadTooLarge()
+        .json(ApiErrorBody { error: LimitError::PayloadTooLarge(max_body_bytes).to_string(), fields: None })
+    }
+
+    // the body as it's read, failing once it goes past the limit, for bodies sent without a length
+    fn limit_payload(req: &mut ServiceRequest, max_body_bytes: usize) {
+      let mut read = 0;
+      let limited: Pin<Box<dyn Stream<Item = Result<Bytes, PayloadError>>>> =
+        Box::pin(req.take_payload().map(move |chunk| {
+          let chunk = chunk?;
+          read += chunk.len();
+          if read > max_body_bytes {
+            return Err(PayloadError::Overflow);
+          }
+          Ok(chunk)
+        }));
+      req.set_payload(Payload::from(limited));
+    }
+
+    // wraps the app inside `require_session`, which has found the user by then
+    // without `RateLimits` in the app data nothing is limited
+    pub async fn limit_requests(
+      mut req: ServiceRequest,
+      next: Next<impl actix_web::body::MessageBody>,
+    ) -> Result<ServiceResponse<EitherBody<impl actix_web::body::MessageBody>>, actix_web::Error> {
+      let Some(limits) = req.app_data::<web::Data<RateLimits>>().cloned() else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+      };
+
+      let content_length = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|content_length| content_length.to_str().ok())
+        .and_then(|content_length| content_length.parse::<usize>().ok());
+      if content_length.is_some_and(|content_length| content_length > limits.max_body_bytes) {
+        return Ok(req.into_response(payload_too_large(limits.max_body_bytes)).map_into_right_body());
+      }
+      limit_payload(&mut req, limits.max_body_bytes);
+
+      // pages and static files are left alone, they can't change anything
+      if req.path().starts_with("/api") {
+        let now = Instant::now();
+        let ip_check = limits.per_ip.check(&client_ip(&req, limits.trust_forwarded_for), now);
+        let user_check = match req.extensions().get::<CurrentUser>() {
+          Some(current_user) => limits.per_user.check(&current_user.username, now),
+          None => Ok(()),
+        };
+        if let Err(retry_after) = ip_check.and(user_check) {
+          return Ok(req.into_response(too_many_requests(retry_after)).map_into_right_body());
+        }
+      }
+      next.call(req).await.map(ServiceResponse::map_into_left_body)
+    }
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use std::time::{Duration, Instant};
+
+  use actix_web::http::{header, StatusCode};
+  use actix_web::test::{call_service, init_service, read_body, TestRequest};
+  use actix_web::{middleware, web, App};
+
+  use super::{limit_requests, RateLimiter, RateLimits};
+
+  #[test]
+  fn limiter_counts_each_key_within_its_window() {
+    let limiter = RateLimiter::new(2, Duration::from_secs(60));
+    let start = Instant::now();
+
+    assert!(limiter.check("a", start).is_ok());
+    assert!(limiter.check("a", start + Duration::from_secs(1)).is_ok());
+    assert_eq!(limiter.check("a", start + Duration::from_secs(20)), Err(Duration::from_secs(40)));
+    assert!(limiter.check("b", start + Duration::from_secs(20)).is_ok());
+    assert!(limiter.check("a", start + Duration::from_secs(60)).is_ok());
+  }
+
+  #[actix_web::test]
+  async fn answers_429_with_retry_after_and_413_for_large_bodies() {
+    let limits = web::Data::new(RateLimits {
+      per_ip: RateLimiter::new(2, Duration::from_secs(60)),
+      per_user: RateLimiter::new(100, Duration::from_secs(60)),
+      max_body_bytes: 16,
+      trust_forwarded_for: false,
+    });
+    let app = init_service(
+      App::new()
+        .app_data(limits)
+        .route("/api/add_person_srv", web::post().to(|body: String| async move { body }))
+        .wrap(middleware::from_fn(limit_requests)),
+    )
+    .await;
+    let post = || TestRequest::post().uri("/api/add_person_srv").peer_addr("10.0.0.1:5000".parse().unwrap());
+
+    let too_large = post().set_payload("x".repeat(17)).to_request();
+    assert_eq!(call_service(&app, too_large).await.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let first = call_service(&app, post().set_payload("ok").to_request()).await;
+    assert_eq!(read_body(first).await, "ok");
+    let _ = call_service(&app, post().to_request()).await;
+    let limited = call_service(&app, post().to_request()).await;
+    assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(limited.headers().get(header::RETRY_AFTER).unwrap(), "60");
+  }
+}
@@ -0,0 +1,456 @@
+//monthly team report to print or send as a pdf: the dashboard figures, its charts and the roster for a period
+//the pdf is drawn on the server with the built-in pdf fonts and the charts as png, no outside service or browser
+use leptos::*;
+
+use crate::app::use_period;
+
+pub const REPORT_PATH: &str = "/report";
+pub const REPORT_PDF_PATH: &str = "/api/report/pdf";
+
+const REPORT_LINKS_STYLE: &str = "w-full max-w-[53rem] mx-auto flex flex-row justify-end gap-6 px-2 mt-4";
+const REPORT_LINK_STYLE: &str = "text-stone-400 hover:text-white";
+
+// links to the report of the period on the dashboard
+#[component]
+pub fn ReportLinks() -> impl IntoView {
+  let period = use_period();
+
+  view! {
+    <div class=REPORT_LINKS_STYLE>
+      // plain pages of the server, not routes of the app
+      <a class=REPORT_LINK_STYLE rel="external" target="_blank"
+        href=move || format!("{}{}", REPORT_PATH, period.get().query())
+      >
+        "Printable report"
+      </a>
+      <a class=REPORT_LINK_STYLE rel="external" download
+        href=move || format!("{}{}", REPORT_PDF_PATH, period.get().query())
+      >
+        "Download PDF"
+      </a>
+    </div>
+  }
+}
+
+cfg_if::cfg_if! {
+  if #[cfg(feature = "ssr")] {
+    use actix_web::http::header;
+    use actix_web::{get, web, HttpResponse};
+    use chrono::Local;
+    use printpdf::image_crate::codecs::jpeg::JpegEncoder;
+    use printpdf::image_crate::{self, ImageFormat, RgbImage};
+    use printpdf::{
+      BuiltinFont, Color, ColorBits, ColorSpace, Image, ImageFilter, ImageTransform, ImageXObject, IndirectFontRef, Mm,
+      PdfDocument, PdfDocumentReference, PdfLayerReference, Px, Rgb,
+    };
+
+    use crate::app::chart_export::{svg_to_png, DashboardChartKind};
+    use crate::app::compensation::{compensation_charts, format_amount};
+    use crate::app::history::{date_of, format_delta, history_chart_svg, last_months, members_on, parse_month};
+    use crate::app::openapi::escape_html;
+    use crate::app::rest_api::ApiError;
+    use crate::app::serv_functions::retrieve_redacted_team;
+    use crate::app::{
+      get_dashboard_aggregates, headcount_chart, CompensationStats, CurrentUser, DashboardStats, MonthDelta, Period,
+      Person, RenderError, TeamHistory, DEFAULT_HISTORY_RANGE,
+    };
+
+    pub const ROSTER_COLUMNS: [&str; 6] = ["Name", "Title", "Level", "Compensation", "Joined", "Manager"];
+
+    // a4 in millimetres
+    const PAGE_WIDTH: f32 = 210.0;
+    const PAGE_HEIGHT: f32 = 297.0;
+    const PAGE_MARGIN: f32 = 15.0;
+    // left edge of each roster column and the characters that fit in it
+    const ROSTER_LAYOUT: [(f32, usize); 6] = [(15.0, 24), (58.0, 22), (98.0, 12), (120.0, 14), (146.0, 10), (166.0, 16)];
+    const ROW_HEIGHT: f32 = 6.0;
+    // the charts are drawn at twice their size so the png stays sharp on paper
+    const CHART_SCALE: u32 = 2;
+    // background of the dashboard chart cards, the dark theme's text needs it
+    const CHART_BACKGROUND: [u8; 3] = [41, 41, 41];
+
+    pub struct ReportData {
+      pub period: Period,
+      pub prepared_for: String,
+      pub generated_at: String,
+      pub stats: DashboardStats,
+      pub deltas: Option<MonthDelta>,
+      pub charts: Vec<(DashboardChartKind, String)>,
+      // the members on the team at the end of the period, by name
+      pub roster: Vec<Person>,
+    }
+
+    impl ReportData {
+      // title, value and change since last month of the dashboard widgets
+      pub fn widgets(&self) -> Vec<(&'static str, String, Option<String>)> {
+        vec![
+          ("Team Members", self.stats.members.to_string(), self.deltas.map(|deltas| format_delta(deltas.headcount, false))),
+          ("Monthly Team Cost", self.stats.total_cost_label(), self.deltas.and_then(|deltas| deltas.cost).map(|cost| format_delta(cost, true))),
+          ("Just Joined", self.stats.latest_member.clone().unwrap_or_default(), None),
+        ]
+      }
+    }
+
+    // the cells of a member's row, in `ROSTER_COLUMNS` order
+    pub fn roster_row(person: &Person) -> [String; 6] {
+      let compensation = match person.compensation {
+        Some(compensation) => format_amount(i64::from(compensation)),
+        None => String::from("Restricted"),
+      };
+      let joined = date_of(&person.joined_date).map_or_else(|| person.joined_date.clone(), |joined| joined.to_string());
+      [
+        person.name.clone(),
+        person.title.clone(),
+        person.level.clone(),
+        compensation,
+        joined,
+        person.manager.clone().unwrap_or_default(),
+      ]
+    }
+
+    // everything on the report, as the dashboard shows it to `current_user` for `period`
+    pub async fn load_report(current_user: &CurrentUser, period: Period) -> Result<ReportData, ApiError> {
+      let aggregates = get_dashboard_aggregates(current_user.username.clone(), period.as_of()).await?;
+      let stats = DashboardStats::new(aggregates, current_user);
+      let (persons, departures) = retrieve_redacted_team(current_user).await;
+      let (from, to) = last_months(DEFAULT_HISTORY_RANGE, period.to);
+      let history = match (parse_month(&from), parse_month(&to)) {
+        (Some(from), Some(to)) => TeamHistory::new(&persons, &departures, from, to),
+        _ => TeamHistory::default(),
+      };
+      let mut roster = members_on(&persons, &departures, period.as_of());
+      roster.sort_by_key(|person| person.name.to_lowercase());
+
+      let mut charts = Vec::new();
+      if !stats.titles.is_empty() {
+        if let Ok(svg) = headcount_chart(&stats).svg() {
+          charts.push((DashboardChartKind::Headcount, svg));
+        }
+      }
+      if let Some(svg) = history_chart_svg(&history) {
+        charts.push((DashboardChartKind::History, svg));
+      }
+      charts.extend(compensation_charts(&CompensationStats::new(&roster)));
+
+      Ok(ReportData {
+        period,
+        prepared_for: current_user.username.clone(),
+        generated_at: Local::now().format("%Y-%m-%d %H:%M").to_string(),
+        stats,
+        deltas: history.month_over_month(),
+        charts,
+        roster,
+      })
+    }
+
+    // one page of html with its own print styles, the charts inline
+    pub fn report_html(report: &ReportData) -> String {
+      let widgets: String = report.widgets().into_iter().map(|(title, value, delta)| {
+        format!(
+          "<div class=\"widget\"><div class=\"value\">{}</div><div>{}</div><div class=\"delta\">{}</div></div>",
+          escape_html(&value),
+          title,
+          escape_html(&delta.unwrap_or_default()),
+        )
+      }).collect();
+      let charts: String = report.charts.iter().map(|(kind, svg)| {
+        format!("<figure><h2>{}</h2><div class=\"chart\">{}</div></figure>", kind.title(), svg)
+      }).collect();
+      let header: String = ROSTER_COLUMNS.iter().map(|column| format!("<th>{}</th>", column)).collect();
+      let rows: String = report.roster.iter().map(|person| {
+        let cells: String = roster_row(person).iter().map(|cell| format!("<td>{}</td>", escape_html(cell))).collect();
+        format!("<tr>{}</tr>", cells)
+      }).collect();
+
+      format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Team Report {period}</title>\
+        <style>@page{{size:A4;margin:15mm}}\
+        body{{font-family:sans-serif;color:#111;max-width:52rem;margin:2rem auto;padding:0 1rem}}\
+        nav{{display:flex;gap:1.5rem;margin-bottom:2rem}}\
+        .widgets{{display:grid;grid-template-columns:repeat(3,1fr);gap:1rem;margin:2rem 0}}\
+        .widget{{border:1px solid #ccc;border-radius:4px;padding:1rem}}.value{{font-size:1.8rem}}.delta{{color:#666;font-size:.85rem}}\
+        figure{{margin:0 0 2rem;break-inside:avoid}}h2{{font-size:1.1rem}}\
+        .chart{{background:#292929;border-radius:4px;padding:1rem;-webkit-print-color-adjust:exact;print-color-adjust:exact}}\
+        .chart svg{{width:100%;height:auto}}\
+        table{{width:100%;border-collapse:collapse;font-size:.85rem}}th,td{{text-align:left;padding:.3rem;border-bottom:1px solid #ddd}}\
+        thead{{display:table-header-group}}tr{{break-inside:avoid}}\
+        @media print{{nav{{display:none}}body{{margin:0;max-width:none}}}}</style></head>\
+        <body><nav><a href=\"/{query}\">Back to the dashboard</a><a href=\"{pdf_path}{query}\" download>Download PDF</a></nav>\
+        <header><h1>Team Report</h1><p>{period}</p><p class=\"delta\">Prepared for {prepared_for} on {generated_at}</p></header>\
+        <section class=\"widgets\">{widgets}</section>{charts}\
+        <h2>Roster</h2><table><thead><tr>{header}</tr></thead><tbody>{rows}</tbody></table></body></html>",
+        period = escape_html(&report.period.label()),
+        query = escape_html(&report.period.query()),
+        pdf_path = REPORT_PDF_PATH,
+        prepared_for = escape_html(&report.prepared_for),
+        generated_at = report.generated_at,
+        widgets = widgets,
+        charts = charts,
+        header = header,
+        rows = rows,
+      )
+    }
+
+    // pages of the pdf, written top to bottom
+    struct PdfPages {
+      document: PdfDocumentReference,
+      layer: PdfLayerReference,
+      font: IndirectFontRef,
+      bold_font: IndirectFontRef,
+      // distance of the next line from the bottom of the page
+      y: f32,
+    }
+
+    impl PdfPages {
+      fn new(title: &str) -> Result<PdfPages, RenderError> {
+        let (document, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "report");
+        let font = document.add_builtin_font(BuiltinFont::Helvetica).map_err(|_| RenderError::RenderFailure)?;
+        let bold_font = document.add_builtin_font(BuiltinFont::HelveticaBold).map_err(|_| RenderError::RenderFailure)?;
+        let layer = document.get_page(page).get_layer(layer);
+        Ok(PdfPages { document, layer, font, bold_font, y: PAGE_HEIGHT - PAGE_MARGIN })
+      }
+
+      // starts a new page when `height` no longer fits on this one
+      fn make_room(&mut self, height: f32) -> bool {
+        if self.y - height >= PAGE_MARGIN {
+          return false;
+        }
+        let (page, layer) = self.document.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "report");
+        self.layer = self.document.get_page(page).get_layer(layer);
+        self.y = PAGE_HEIGHT - PAGE_MARGIN;
+        true
+      }
+
+      fn text(&self, text: &str, size: f32, x: f32, bold: bool) {
+        let font = if bold { &self.bold_font } else { &self.font };
+        self.layer.set_fill_color(Color::Rgb(Rgb::new(0.07, 0.07, 0.07, None)));
+        self.layer.use_text(text, size, Mm(x), Mm(self.y), font);
+      }
+
+      fn line(&mut self, text: &str, size: f32, bold: bool) {
+        let height = size * 0.5;
+        self.make_room(height);
+        self.y -= height;
+        self.text(text, size, PAGE_MARGIN, bold);
+      }
+
+      // the chart across the page on the dark card it has on the dashboard
+      fn chart(&mut self, title: &str, svg: &str) -> Result<(), RenderError> {
+        let png = svg_to_png(&scaled_svg(svg, CHART_SCALE))?;
+        let (pixel_width, pixel_height, jpeg) = flattened_jpeg(&png)?;
+        let image = Image::from(ImageXObject {
+          width: Px(pixel_width as usize),
+          height: Px(pixel_height as usize),
+          color_space: ColorSpace::Rgb,
+          bits_per_component: ColorBits::Bit8,
+          interpolate: true,
+          image_data: jpeg,
+          image_filter: Some(ImageFilter::DCT),
+          smask: None,
+          clipping_bbox: None,
+        });
+        let width = PAGE_WIDTH - 2.0 * PAGE_MARGIN;
+        let height = width * pixel_height as f32 / pixel_width as f32;
+
+        self.make_room(height + 12.0);
+        self.line(title, 12.0, true);
+        self.y -= 3.0 + height;
+        image.add_to_layer(self.layer.clone(), ImageTransform {
+          translate_x: Some(Mm(PAGE_MARGIN)),
+          translate_y: Some(Mm(self.y)),
+          dpi: Some(pixel_width as f32 * 25.4 / width),
+          ..ImageTransform::default()
+        });
+        self.y -= 6.0;
+        Ok(())
+      }
+
+      fn roster_row(&mut self, cells: &[String], bold: bool) {
+        self.y -= ROW_HEIGHT;
+        for (cell, (x, max_chars)) in cells.iter().zip(ROSTER_LAYOUT) {
+          self.text(&clipped(cell, max_chars), 9.0, x, bold);
+        }
+      }
+    }
+
+    // the svg drawn `scale` times larger, its viewbox keeps the drawing the same
+    fn scaled_svg(svg: &str, scale: u32) -> String {
+      let attribute = |name: &str| -> Option<(String, u32)> {
+        let start = svg.find(&format!("{}=\"", name))? + name.len() + 2;
+        let value = &svg[start..start + svg[start..].find('"')?];
+        Some((format!("{}=\"{}\"", name, value), value.parse::<f32>().ok()? as u32))
+      };
+      match (attribute("width"), attribute("height")) {
+        (Some((width, width_value)), Some((height, height_value))) => svg
+          .replacen(&width, &format!("width=\"{}\"", width_value * scale), 1)
+          .replacen(&height, &format!("height=\"{}\"", height_value * scale), 1),
+        _ => svg.to_string(),
+      }
+    }
+
+    // the png on the card background as a jpeg, printpdf doesn't compress images itself in debug builds
+    // and its soft masks for transparency come out the wrong size
+    fn flattened_jpeg(png: &[u8]) -> Result<(u32, u32, Vec<u8>), RenderError> {
+      let chart = image_crate::load_from_memory_with_format(png, ImageFormat::Png)
+        .map_err(|_| RenderError::RenderFailure)?
+        .to_rgba8();
+      let flattened = RgbImage::from_fn(chart.width(), chart.height(), |x, y| {
+        let [red, green, blue, alpha] = chart.get_pixel(x, y).0;
+        let blend = |color: u8, background: u8| {
+          ((u16::from(color) * u16::from(alpha) + u16::from(background) * (255 - u16::from(alpha))) / 255) as u8
+        };
+        image_crate::Rgb([
+          blend(red, CHART_BACKGROUND[0]),
+          blend(green, CHART_BACKGROUND[1]),
+          blend(blue, CHART_BACKGROUND[2]),
+        ])
+      });
+      let mut jpeg = Vec::new();
+      JpegEncoder::new_with_quality(&mut jpeg, 90)
+        .encode_image(&flattened)
+        .map_err(|_| RenderError::RenderFailure)?;
+      Ok((flattened.width(), flattened.height(), jpeg))
+    }
+
+    // the built-in fonts have no width table here, so long cells are cut by characters
+    fn clipped(text: &str, max_chars: usize) -> String {
+      match text.chars().count() > max_chars {
+        true => format!("{}...", text.chars().take(max_chars - 3).collect::<String>()),
+        false => text.to_string(),
+      }
+    }
+
+    pub fn report_pdf(report: &ReportData) -> Result<Vec<u8>, RenderError> {
+      let title = format!("Team Report {}", report.period.label());
+      let mut pages = PdfPages::new(&title)?;
+      pages.line("Team Report", 24.0, true);
+      pages.y -= 4.0;
+      pages.line(&report.period.label(), 14.0, false);
+      pages.line(&format!("Prepared for {} on {}", report.prepared_for, report.generated_at), 9.0, false);
+      pages.y -= 6.0;
+      for (title, value, delta) in report.widgets() {
+        let line = match delta {
+          Some(delta) => format!("{}: {} ({})", title, value, delta),
+          None => format!("{}: {}", title, value),
+        };
+        pages.line(&line, 12.0, false);
+      }
+      pages.y -= 6.0;
+      for (kind, svg) in &report.charts {
+        pages.chart(kind.title(), svg)?;
+      }
+
+      let header = ROSTER_COLUMNS.map(String::from);
+      pages.make_room(12.0 + ROW_HEIGHT * 2.0);
+      pages.line("Roster", 12.0, true);
+      pages.roster_row(&header, true);
+      for person in &report.roster {
+        // the header goes on top of every page of the table
+        if pages.make_room(ROW_HEIGHT) {
+          pages.roster_row(&header, true);
+        }
+        pages.roster_row(&roster_row(person), false);
+      }
+      pages.document.save_to_bytes().map_err(|e| {
+        println!("Error in writing the report pdf: {:?}", e);
+        RenderError::RenderFailure
+      })
+    }
+
+    // the period params of the dashboard url
+    fn report_period(query: web::Query<Vec<(String, String)>>) -> Period {
+      let mut params = leptos_router::ParamsMap::new();
+      for (key, value) in query.into_inner() {
+        params.insert(key, value);
+      }
+      Period::from_query(&params, Local::now().date_naive())
+    }
+
+    #[get("/report")]
+    pub async fn report_page(
+      current_user: CurrentUser,
+      query: web::Query<Vec<(String, String)>>,
+    ) -> Result<HttpResponse, ApiError> {
+      let report = load_report(&current_user, report_period(query)).await?;
+      Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(report_html(&report)))
+    }
+
+    #[get("/api/report/pdf")]
+    pub async fn report_pdf_download(
+      current_user: CurrentUser,
+      query: web::Query<Vec<(String, String)>>,
+    ) -> Result<HttpResponse, ApiError> {
+      let report = load_report(&current_user, report_period(query)).await?;
+      let pdf = report_pdf(&report)?;
+      // e.g. team-report-2024-07.pdf, named after the first month of the period
+      let filename = format!("team-report-{}.pdf", report.period.from.format("%Y-%m"));
+      Ok(HttpResponse::Ok()
+        .content_type("application/pdf")
+        .insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)))
+        .body(pdf))
+    }
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use chrono::NaiveDate;
+
+  use super::{report_html, report_pdf, roster_row, ReportData};
+  use crate::app::dashboard::{headcount_chart, DashboardStats, TitleCount};
+  use crate::app::{DashboardChartKind, MonthDelta, Period, PeriodKind, Person};
+
+  fn report(roster_size: usize) -> ReportData {
+    let stats = DashboardStats {
+      members: roster_size,
+      total_cost: None,
+      latest_member: Some(String::from("Ann <QA>")),
+      titles: vec![TitleCount { title: String::from("Engineer"), members: roster_size, names: Vec::new() }],
+    };
+    let roster = (0..roster_size)
+      .map(|number| Person {
+        joined_date: String::from("2024-03-04 09:00:00.000 +00:00"),
+        ..Person::test_member(&format!("Member {}", number), "Engineer", "Senior", 4000)
+      })
+      .collect();
+    ReportData {
+      period: Period::containing(PeriodKind::Quarter, NaiveDate::from_ymd_opt(2024, 8, 1).unwrap()),
+      prepared_for: String::from("lee"),
+      generated_at: String::from("2024-10-01 09:00"),
+      charts: vec![(DashboardChartKind::Headcount, headcount_chart(&stats).svg().unwrap())],
+      stats,
+      deltas: Some(MonthDelta { headcount: 2, cost: None }),
+      roster,
+    }
+  }
+
+  #[test]
+  fn report_has_widgets_charts_and_roster() {
+    let html = report_html(&report(3));
+    assert!(html.contains("<p>Q3 2024</p>"));
+    assert!(html.contains("Ann &lt;QA&gt;") && html.contains("Restricted") && html.contains("+2 vs last month"));
+    assert!(html.contains("<h2>Members per Title</h2>"));
+    assert_eq!(html.matches("<td>Member ").count(), 3);
+    assert!(html.contains("href=\"/api/report/pdf?period=quarter&amp;from=2024-07\""));
+
+    let redacted = Person {
+      compensation: None,
+      joined_date: String::from("2024-01-02"),
+      ..Person::test_member("Di", "Designer", "Junior", 4000)
+    };
+    assert_eq!(roster_row(&redacted)[3..5], [String::from("Restricted"), String::from("2024-01-02")]);
+  }
+
+  #[test]
+  fn long_rosters_run_onto_more_pdf_pages() {
+    let page_count = |roster_size| {
+      let pdf = report_pdf(&report(roster_size)).unwrap();
+      assert!(pdf.starts_with(b"%PDF-"));
+      printpdf::lopdf::Document::load_mem(&pdf).unwrap().get_pages().len()
+    };
+    assert_eq!(page_count(1), 1);
+    assert!(page_count(120) >= 3);
+  }
+}
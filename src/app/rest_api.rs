@@ -0,0 +1,305 @@
+//versioned json api for scripts, next to the leptos server functions under /api
+//uses the same repository functions and validation rules as the server functions
+cfg_if::cfg_if! {
+  if #[cfg(feature = "ssr")] {
+
+    use actix_web::http::{header, StatusCode};
+    use actix_web::{web, HttpResponse, ResponseError};
+    use serde::Serialize;
+    use thiserror::Error;
+    use validator::{Validate, ValidationErrors};
+
+    use crate::app::serv_functions::{
+      add_new_person, delete_team_person, edit_team_person, find_team_person, retrieve_all_persons,
+    };
+    use crate::app::{AddPersonRequest, AuthError, CurrentUser, PatchPersonRequest, Person, PersonError, RenderError};
+
+    pub const API_V1_PATH: &str = "/api/v1";
+
+    #[derive(Error, Debug)]
+    pub enum ApiError {
+      #[error(transparent)]
+      Person(#[from] PersonError),
+      #[error("invalid request body: {0}")]
+      InvalidBody(String),
+      #[error("validation failed")]
+      Validation(#[from] ValidationErrors),
+      #[error("invalid query: {0}")]
+      InvalidQuery(String),
+      #[error(transparent)]
+      Auth(#[from] AuthError),
+      #[error(transparent)]
+      Render(#[from] RenderError),
+    }
+
+    // body of every error response
+    #[derive(Debug, Serialize, utoipa::ToSchema)]
+    pub struct ApiErrorBody {
+      pub error: String,
+      // per-field validator errors, only present for 422 responses
+      #[serde(skip_serializing_if = "Option::is_none")]
+      #[schema(value_type = Option<Object>)]
+      pub fields: Option<ValidationErrors>,
+    }
+
+    impl ResponseError for ApiError {
+      fn status_code(&self) -> StatusCode {
+        match self {
+          ApiError::Person(PersonError::PersonNotFound) => StatusCode::NOT_FOUND,
+          ApiError::Person(_) => StatusCode::INTERNAL_SERVER_ERROR,
+          ApiError::InvalidBody(_) | ApiError::InvalidQuery(_) => StatusCode::BAD_REQUEST,
+          ApiError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+          ApiError::Auth(AuthError::Unauthenticated | AuthError::InvalidToken) => StatusCode::UNAUTHORIZED,
+          ApiError::Auth(AuthError::Forbidden | AuthError::CsrfRejected) => StatusCode::FORBIDDEN,
+          ApiError::Auth(_) => StatusCode::INTERNAL_SERVER_ERROR,
+          ApiError::Render(RenderError::UnknownChart(_) | RenderError::NoData) => StatusCode::NOT_FOUND,
+          ApiError::Render(RenderError::RenderFailure) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+      }
+
+      fn error_response(&self) -> HttpResponse {
+        let fields = match self {
+          ApiError::Validation(validation_errors) => Some(validation_errors.clone()),
+          _ => None,
+        };
+        HttpResponse::build(self.status_code()).json(ApiErrorBody {
+          error: self.to_string(),
+          fields,
+        })
+      }
+    }
+
+    // mounted under `API_V1_PATH`
+    pub fn configure(cfg: &mut web::ServiceConfig) {
+      cfg
+        .app_data(web::JsonConfig::default().error_handler(|err, _req| {
+          ApiError::InvalidBody(err.to_string()).into()
+        }))
+        .service(
+          web::resource("/persons")
+            .route(web::get().to(list_persons))
+            .route(web::post().to(create_person)),
+        )
+        .service(
+          web::resource("/persons/{uuid}")
+            .route(web::get().to(show_person))
+            .route(web::patch().to(patch_person))
+            .route(web::delete().to(remove_person)),
+        );
+    }
+
+    pub fn person_location(uuid: &str) -> String {
+      format!("{}/persons/{}", API_V1_PATH, uuid)
+    }
+
+    #[utoipa::path(
+      get,
+      path = "/api/v1/persons",
+      tag = "persons",
+      responses(
+        (status = 200, description = "all members, newest first, pay is null for members the caller doesn't manage", body = [Person]),
+        (status = 401, description = "not logged in", body = ApiErrorBody),
+      ),
+    )]
+    pub async fn list_persons(current_user: CurrentUser) -> Result<HttpResponse, ApiError> {
+      let persons: Vec<Person> = retrieve_all_persons()
+        .await
+        .into_iter()
+        .map(|person| current_user.redact(person))
+        .collect();
+      Ok(HttpResponse::Ok().json(persons))
+    }
+
+    #[utoipa::path(
+      post,
+      path = "/api/v1/persons",
+      tag = "persons",
+      request_body = AddPersonRequest,
+      responses(
+        (status = 201, description = "member created", body = Person,
+          headers(("Location" = String, description = "url of the new member"))),
+        (status = 400, description = "body is not a valid add request", body = ApiErrorBody),
+        (status = 401, description = "not logged in", body = ApiErrorBody),
+        (status = 403, description = "only hr admins add members", body = ApiErrorBody),
+        (status = 422, description = "a field failed validation", body = ApiErrorBody),
+        (status = 500, description = "member could not be saved", body = ApiErrorBody),
+      ),
+    )]
+    pub async fn create_person(current_user: CurrentUser, body: web::Json<AddPersonRequest>) -> Result<HttpResponse, ApiError> {
+      if !current_user.can_add_and_delete() {
+        return Err(AuthError::Forbidden.into());
+      }
+      let add_person_request = body.into_inner();
+      add_person_request.validate()?;
+
+      let created_person = add_new_person(
+        add_person_request.name,
+        add_person_request.title,
+        add_person_request.level,
+        add_person_request.compensation,
+        add_person_request.manager,
+      )
+      .await
+      .ok_or(PersonError::PersonCreationFailure)?;
+
+      Ok(HttpResponse::Created()
+        .insert_header((header::LOCATION, person_location(&created_person.uuid)))
+        .json(created_person))
+    }
+
+    #[utoipa::path(
+      get,
+      path = "/api/v1/persons/{uuid}",
+      tag = "persons",
+      params(("uuid" = String, Path, description = "id of the member")),
+      responses(
+        (status = 200, description = "the member, pay is null unless the caller manages them", body = Person),
+        (status = 401, description = "not logged in", body = ApiErrorBody),
+        (status = 404, description = "no member with this id", body = ApiErrorBody),
+        (status = 500, description = "member could not be read", body = ApiErrorBody),
+      ),
+    )]
+    pub async fn show_person(current_user: CurrentUser, path: web::Path<String>) -> Result<HttpResponse, ApiError> {
+      let found_person = find_team_person(path.into_inner())
+        .await?
+        .ok_or(PersonError::PersonNotFound)?;
+      Ok(HttpResponse::Ok().json(current_user.redact(found_person)))
+    }
+
+    #[utoipa::path(
+      patch,
+      path = "/api/v1/persons/{uuid}",
+      tag = "persons",
+      params(("uuid" = String, Path, description = "id of the member")),
+      request_body = PatchPersonRequest,
+      responses(
+        (status = 200, description = "the updated member", body = Person),
+        (status = 400, description = "body is not a valid patch", body = ApiErrorBody),
+        (status = 401, description = "not logged in", body = ApiErrorBody),
+        (status = 403, description = "the caller doesn't manage this member", body = ApiErrorBody),
+        (status = 404, description = "no member with this id", body = ApiErrorBody),
+        (status = 422, description = "a field failed validation", body = ApiErrorBody),
+        (status = 500, description = "member could not be updated", body = ApiErrorBody),
+      ),
+    )]
+    pub async fn patch_person(
+      current_user: CurrentUser,
+      path: web::Path<String>,
+      body: web::Json<PatchPersonRequest>,
+    ) -> Result<HttpResponse, ApiError> {
+      let found_person = find_team_person(path.into_inner())
+        .await?
+        .ok_or(PersonError::PersonNotFound)?;
+
+      let edit_person_request = current_user.authorize_edit(&found_person, body.into_inner().apply_to(&found_person))?;
+      edit_person_request.validate()?;
+
+      let updated_person = edit_team_person(
+        edit_person_request.uuid,
+        edit_person_request.title,
+        edit_person_request.level,
+        edit_person_request.compensation,
+        edit_person_request.manager,
+      )
+      .await?
+      .ok_or(PersonError::PersonUpdateFailure)?;
+      Ok(HttpResponse::Ok().json(updated_person))
+    }
+
+    #[utoipa::path(
+      delete,
+      path = "/api/v1/persons/{uuid}",
+      tag = "persons",
+      params(("uuid" = String, Path, description = "id of the member")),
+      responses(
+        (status = 204, description = "member deleted"),
+        (status = 401, description = "not logged in", body = ApiErrorBody),
+        (status = 403, description = "only hr admins delete members", body = ApiErrorBody),
+        (status = 404, description = "no member with this id", body = ApiErrorBody),
+        (status = 500, description = "member could not be deleted", body = ApiErrorBody),
+      ),
+    )]
+    pub async fn remove_person(current_user: CurrentUser, path: web::Path<String>) -> Result<HttpResponse, ApiError> {
+      if !current_user.can_add_and_delete() {
+        return Err(AuthError::Forbidden.into());
+      }
+      delete_team_person(path.into_inner())
+        .await?
+        .ok_or(PersonError::PersonNotFound)?;
+      Ok(HttpResponse::NoContent().finish())
+    }
+  }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+  use actix_web::http::{header, StatusCode};
+  use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+  use actix_web::{web, App, HttpMessage};
+  use serde_json::{json, Value};
+
+  use super::{configure, API_V1_PATH};
+  use crate::app::{CurrentUser, Role};
+
+  // `role` is the user `require_session` would have found, none when not logged in
+  // no database runs here, so a lookup finds no member
+  async fn send(request: TestRequest, role: Option<Role>) -> (StatusCode, Value) {
+    let app = init_service(App::new().service(web::scope(API_V1_PATH).configure(configure))).await;
+    let req = request.to_request();
+    if let Some(role) = role {
+      req.extensions_mut().insert(CurrentUser { username: "lee".into(), role, token_scope: None });
+    }
+    let res = call_service(&app, req).await;
+    (res.status(), read_body_json(res).await)
+  }
+
+  fn valid_person() -> Value {
+    json!({ "name": "Ann", "title": "Engineer", "level": "L2", "compensation": 5000 })
+  }
+
+  #[actix_web::test]
+  async fn handlers_answer_with_error_bodies() {
+    let persons = format!("{}/persons", API_V1_PATH);
+    let person = format!("{}/persons/missing", API_V1_PATH);
+    let not_json = |request: TestRequest| {
+      request.insert_header((header::CONTENT_TYPE, "application/json")).set_payload("not json")
+    };
+
+    let unauthenticated = [
+      TestRequest::get().uri(&persons),
+      TestRequest::post().uri(&persons).set_json(valid_person()),
+      TestRequest::get().uri(&person),
+      TestRequest::patch().uri(&person).set_json(json!({})),
+      TestRequest::delete().uri(&person),
+    ];
+    for request in unauthenticated {
+      assert_eq!(send(request, None).await.0, StatusCode::UNAUTHORIZED);
+    }
+
+    assert_eq!(send(not_json(TestRequest::post().uri(&persons)), Some(Role::HrAdmin)).await.0, StatusCode::BAD_REQUEST);
+    assert_eq!(send(not_json(TestRequest::patch().uri(&person)), Some(Role::HrAdmin)).await.0, StatusCode::BAD_REQUEST);
+
+    let forbidden = [TestRequest::post().uri(&persons).set_json(valid_person()), TestRequest::delete().uri(&person)];
+    for request in forbidden {
+      assert_eq!(send(request, Some(Role::Manager)).await.0, StatusCode::FORBIDDEN);
+    }
+
+    let mut low_pay = valid_person();
+    low_pay["compensation"] = json!(10);
+    let (status, body) = send(TestRequest::post().uri(&persons).set_json(low_pay), Some(Role::HrAdmin)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["fields"]["compensation"].is_array());
+
+    // no database is connected here, so the lookup fails instead of finding nothing
+    let unreadable = [
+      TestRequest::get().uri(&person),
+      TestRequest::patch().uri(&person).set_json(json!({ "title": "Lead" })),
+      TestRequest::delete().uri(&person),
+    ];
+    for request in unreadable {
+      let (status, body) = send(request, Some(Role::HrAdmin)).await;
+      assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+      assert!(body["error"].is_string());
+    }
+  }
+}
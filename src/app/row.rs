@@ -1,5 +1,6 @@
-use crate::app::{Person, EditPersonModal, ShowPersonModal, ToastMessage};
+use crate::app::{Person, PersonStore, EditPersonModal, ToastMessage, use_current_user, is_modal_open, modal_href, person_href, EDIT_MODAL};
 use leptos::*;
+use leptos_router::*;
 use std::rc::Rc;
 
 const ROW_STYLE: &str = "bg-[#283653] rounded px-10 py-5 mb-4 flex flex-row text-left items-center transition-all duration-1000 ease-in-out";
@@ -9,56 +10,66 @@ const SHOW_ICON_STYLE: &str = "bg-transparent border-2 border-white px-2.5 round
 #[component]
 pub fn PersonRow(
   person: Rc<Person>, 
-  person_resource: Resource<(), Result<Vec<Person>, ServerFnError>>,
   set_if_show_toast: WriteSignal<bool>,
   set_toast_message: WriteSignal<ToastMessage>,
+  // uuids ticked for a bulk change
+  selected: RwSignal<Vec<String>>,
 ) -> impl IntoView {
-  let (if_show_info_modal
-    , set_if_show_info_modal) = create_signal(false);
-  let (if_show_edit_modal
-    , set_if_show_edit_modal) = create_signal(false);
+  // the edit modal is opened through a link, see `modal_href`
+  let if_show_edit_modal = is_modal_open(EDIT_MODAL, Some(person.uuid.clone()));
 
-  let on_show_edit = move |_| {
-    set_if_show_edit_modal.set(true);
+  let edit_person = person.clone();
+  // rows added optimistically have no db id yet, so they cannot be opened
+  let is_pending = PersonStore::is_pending(&person);
+  // only members the user manages can be edited or picked for a bulk change
+  let current_user = use_current_user();
+  let managed_person = person.clone();
+  let edit_href = store_value(modal_href(EDIT_MODAL, Some(&person.uuid)));
+  let can_edit = move || current_user.with(|current_user| {
+    current_user.as_ref().is_some_and(|current_user| current_user.can_edit(&managed_person))
+  });
+
+  let uuid = person.uuid.clone();
+  let is_selected = {
+    let uuid = uuid.clone();
+    move || selected.with(|selected| selected.contains(&uuid))
   };
-  let on_show_info = move |_| {
-    set_if_show_info_modal.set(true);
+  let on_select = move |event| {
+    let checked = event_target_checked(&event);
+    selected.update(|selected| {
+      selected.retain(|selected_uuid| selected_uuid != &uuid);
+      if checked {
+        selected.push(uuid.clone());
+      }
+    });
   };
 
-  let edit_person = person.clone();
-  let team_person = person.clone();
-
   view! {
-    <Show when= move || { if_show_info_modal.get() }>
-      <ShowPersonModal 
-        person= team_person.clone()
-        set_if_show_info_modal
-        set_if_show_deleted= set_if_show_toast
-        person_resource
-        set_toast_message 
-      />
-    </Show>
     <Show when= move || { if_show_edit_modal.get()}>
       <EditPersonModal 
         person= edit_person.clone()
-        set_if_show_edit_modal
         set_if_show_toast
-        person_resource
         set_toast_message
       />
     </Show>
     <div class=ROW_STYLE>
+      <input type="checkbox" class="mr-6 w-4 h-4" aria-label="select member"
+        disabled={let can_edit = can_edit.clone(); move || is_pending || !can_edit()} prop:checked=is_selected on:change=on_select/>
       <div class="flex flex-col w-full max-w-[45rem]">
         <p class="font-bold">{&person.name}</p>
         <p class="text-sm text-stone-400">{&person.title}</p>
       </div>
 
-      <div class="flex flex-row">
-        <button class=SHOW_ICON_STYLE on:click= on_show_info>"i"</button>
-        <button class="" on:click= on_show_edit>
-            <img src="assets/edit.png" class="w-[35px] hover:w-[38px] transition-all duration-500" alt="edit image" />
-        </button>
-      </div>
+      <Show when= move || !is_pending>
+        <div class="flex flex-row">
+          <A href=person_href(&person.uuid) class=SHOW_ICON_STYLE>"i"</A>
+          <Show when=can_edit.clone()>
+            <A href=edit_href.get_value()>
+                <img src="assets/edit.png" class="w-[35px] hover:w-[38px] transition-all duration-500" alt="edit image" />
+            </A>
+          </Show>
+        </div>
+      </Show>
     </div>
   }
-}
\ No newline at end of file
+}
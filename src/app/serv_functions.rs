@@ -1,38 +1,110 @@
 #![allow(unused)]
 // use crate::app::Person;
 use leptos::*;
+use leptos::server_fn;
 use serde::*;
 
-use super::{database, errors::ErrorMessage, AddPersonRequest, Person, ResponseErrorTrait, EditPersonRequest, DeletePersonRequest};
+use super::{database, errors::ErrorMessage, AddPersonRequest, Person, ResponseErrorTrait, EditPersonRequest, DeletePersonRequest, ToastMessageType, ImportRow, ImportSummary, BulkAction, BulkRequest, BulkResult, CompensationStats, TeamHistory, DashboardStats};
 
-#[server(GetPersonsSrv, "/api")]
+#[server(name = GetPersonsSrv, prefix = "/api", client = crate::app::CsrfClient)]
 pub async fn get_persons_srv() -> Result<Vec<Person>,ServerFnError> {
-  let persons = retrieve_all_persons().await;
+  let current_user = require_user().await?;
+  // pay the user may not see never leaves the server
+  let persons = retrieve_all_persons().await
+    .into_iter()
+    .map(|person| current_user.redact(person))
+    .collect();
   Ok(persons)
 }
 
-#[server(AddPersonSrv, "/api")]
+// headcount and cost of the dashboard as of the end of `as_of`, a `YYYY-MM-DD` day
+// grouped by the database so the members themselves aren't sent
+#[server(name = GetDashboardStatsSrv, prefix = "/api", client = crate::app::CsrfClient)]
+pub async fn get_dashboard_stats_srv(as_of: String) -> Result<DashboardStats, ServerFnError> {
+  let current_user = require_user().await?;
+  let as_of = parse_as_of(&as_of)?;
+  match get_dashboard_aggregates(current_user.username.clone(), as_of).await {
+    Ok(aggregates) => Ok(DashboardStats::new(aggregates, &current_user)),
+    Err(person_error) => Err(ServerFnError::Response(ErrorMessage::create(person_error))),
+  }
+}
+
+// pay breakdowns for the dashboard as of the end of `as_of`, over the pay the user may see
+#[server(name = GetCompensationStatsSrv, prefix = "/api", client = crate::app::CsrfClient)]
+pub async fn get_compensation_stats_srv(as_of: String) -> Result<CompensationStats, ServerFnError> {
+  let current_user = require_user().await?;
+  let as_of = parse_as_of(&as_of)?;
+  let (persons, departures) = retrieve_redacted_team(&current_user).await;
+  Ok(CompensationStats::new(&members_on(&persons, &departures, as_of)))
+}
+
+// monthly headcount and cost from `from` to `to`, both `YYYY-MM`
+#[server(name = GetTeamHistorySrv, prefix = "/api", client = crate::app::CsrfClient)]
+pub async fn get_team_history_srv(from: String, to: String) -> Result<TeamHistory, ServerFnError> {
+  let current_user = require_user().await?;
+  let Some((from, to)) = parse_history_range(&from, &to) else {
+    return Err(ServerFnError::Args(format!(
+      "months must look like 2024-09, from no later than to and at most {} months apart", MAX_HISTORY_MONTHS,
+    )));
+  };
+  let (persons, departures) = retrieve_redacted_team(&current_user).await;
+  Ok(TeamHistory::new(&persons, &departures, from, to))
+}
+
+#[server(name = GetPersonSrv, prefix = "/api", client = crate::app::CsrfClient)]
+pub async fn get_person_srv(uuid: String) -> Result<Option<Person>, ServerFnError> {
+  let current_user = require_user().await?;
+  // Ok(None) when there is no such member, so the page can answer with a 404
+  let found = find_team_person(uuid).await;
+
+  match found {
+    Ok(found_person) => Ok(found_person.map(|person| current_user.redact(person))),
+    Err(person_error) => Err(ServerFnError::Response(ErrorMessage::create(person_error))),
+  }
+}
+
+#[server(name = AddPersonSrv, prefix = "/api", client = crate::app::CsrfClient)]
 pub async fn add_person_srv(add_person_request: AddPersonRequest) -> Result<Person, ServerFnError> {
+  if !require_user().await?.can_add_and_delete() {
+    return Err(forbidden());
+  }
+  // forms can be posted without the client side checks, so validate again here
+  if let Err(validation_errors) = add_person_request.validate() {
+    return Err(ServerFnError::Args(validation_errors.to_string()));
+  }
+
   let new_person = add_new_person(add_person_request.name, add_person_request.title, add_person_request.level, add_person_request.compensation,
+    add_person_request.manager,
   ).await;
 
   match new_person {
-    Some(created_person) => Ok(created_person),
+    Some(created_person) => {
+      redirect_with_flash(ToastMessageType::NewMemberAdded);
+      Ok(created_person)
+    },
     None => Err(ServerFnError::Args(String::from(
       "Error in creating person",
     ))),
   } 
 }
 
-#[server(DeletePersonSrv, "/api")]
+#[server(name = DeletePersonSrv, prefix = "/api", client = crate::app::CsrfClient)]
 pub async fn delete_person_srv(
   delete_person_request: DeletePersonRequest
 ) -> Result<Person, ServerFnError> {
+  if !require_user().await?.can_add_and_delete() {
+    return Err(forbidden());
+  }
+  if let Err(validation_errors) = delete_person_request.validate() {
+    return Err(ServerFnError::Args(validation_errors.to_string()));
+  }
+
   let deleted_results = delete_team_person(delete_person_request.uuid).await;
 
   match deleted_results {
     Ok(deleted) => {
       if let Some(deleted_person) = deleted {
+        redirect_with_flash(ToastMessageType::MemberDeleted);
         Ok(deleted_person)
       } else {
         Err(ServerFnError::Response(ErrorMessage::create(PersonError::PersonDeleteFailure)))
@@ -42,35 +114,167 @@ pub async fn delete_person_srv(
   }
 }
 
-#[server(EditPersonSrv, "/api")]
+#[server(name = EditPersonSrv, prefix = "/api", client = crate::app::CsrfClient)]
 pub async fn edit_person_srv(edit_person_request: EditPersonRequest) -> Result<Person, ServerFnError> {
+  let current_user = require_user().await?;
+  if let Err(validation_errors) = edit_person_request.validate() {
+    return Err(ServerFnError::Args(validation_errors.to_string()));
+  }
+
+  // managers may only edit their own reports
+  let found_person = match find_team_person(edit_person_request.uuid.clone()).await {
+    Ok(Some(found_person)) => found_person,
+    Ok(None) => return Err(ServerFnError::Response(ErrorMessage::create(PersonError::PersonNotFound))),
+    Err(person_error) => return Err(ServerFnError::Response(ErrorMessage::create(person_error))),
+  };
+  let edit_person_request = match current_user.authorize_edit(&found_person, edit_person_request) {
+    Ok(edit_person_request) => edit_person_request,
+    Err(_) => return Err(forbidden()),
+  };
+
   let updated = edit_team_person(
     edit_person_request.uuid,
     edit_person_request.title,
     edit_person_request.level,
     edit_person_request.compensation,
+    edit_person_request.manager,
   ).await;
 
   match updated {
     Ok(updated_result) => {
       //we have a some in an option of Person
       if let Some(updated_person) = updated_result {
+        redirect_with_flash(ToastMessageType::MemberUpdated);
         Ok(updated_person)
       } else {
-        Err(ServerFnError::Args(ErrorMessage::create(
+        Err(ServerFnError::Response(ErrorMessage::create(
           PersonError::PersonUpdateFailure,
         )))
       }
     }
-    Err(person_error) => Err(ServerFnError::Args(ErrorMessage::create(person_error))),  
+    Err(person_error) => Err(ServerFnError::Response(ErrorMessage::create(person_error))),  
+  }
+}
+
+#[server(name = PreviewImportSrv, prefix = "/api", client = crate::app::CsrfClient)]
+pub async fn preview_import_srv(csv_text: String) -> Result<Vec<ImportRow>, ServerFnError> {
+  if !require_user().await?.can_add_and_delete() {
+    return Err(forbidden());
+  }
+  // nothing is saved, the rows come back with their validation errors for the preview
+  parse_import_csv(&csv_text).map_err(ServerFnError::Args)
+}
+
+#[server(name = ImportPersonsSrv, prefix = "/api", client = crate::app::CsrfClient)]
+pub async fn import_persons_srv(csv_text: String) -> Result<ImportSummary, ServerFnError> {
+  if !require_user().await?.can_add_and_delete() {
+    return Err(forbidden());
+  }
+  // the file is parsed and validated again, only rows that pass are saved
+  let rows = match parse_import_csv(&csv_text) {
+    Ok(rows) => rows,
+    Err(message) => return Err(ServerFnError::Args(message)),
+  };
+  let skipped = rows.iter().filter(|row| !row.is_valid()).count();
+  let add_person_requests: Vec<AddPersonRequest> = rows.into_iter().filter_map(|row| row.request).collect();
+
+  if add_person_requests.is_empty() {
+    return Err(ServerFnError::Args(String::from("no valid rows to import")));
+  }
+
+  match import_team_persons(add_person_requests).await {
+    Ok(imported) => Ok(ImportSummary { imported, skipped }),
+    Err(person_error) => Err(ServerFnError::Response(ErrorMessage::create(person_error))),
+  }
+}
+
+// sent as json, the bulk action is an enum that doesn't fit form encoding
+#[server(name = BulkUpdateSrv, prefix = "/api", input = server_fn::codec::Json, client = crate::app::CsrfClient)]
+pub async fn bulk_update_srv(bulk_request: BulkRequest) -> Result<BulkResult, ServerFnError> {
+  let current_user = require_user().await?;
+  if let Some(selection_error) = bulk_request.selection_error() {
+    return Err(ServerFnError::Args(selection_error));
+  }
+  if bulk_request.action == BulkAction::Delete && !current_user.can_add_and_delete() {
+    return Err(forbidden());
+  }
+
+  // the plan is worked out on the current members, so it is checked against what is actually saved
+  let persons = retrieve_all_persons().await;
+  let plan = BulkPlan::new(&persons, &bulk_request);
+  if plan.missing > 0 {
+    return Err(ServerFnError::Response(ErrorMessage::create(PersonError::PersonNotFound)));
+  }
+  // every selected member has to be one the user may edit
+  let selected_all_editable = persons
+    .iter()
+    .filter(|person| bulk_request.uuids.contains(&person.uuid))
+    .all(|person| current_user.can_edit(person));
+  if !selected_all_editable {
+    return Err(forbidden());
+  }
+  if !plan.invalid.is_empty() {
+    return Err(ServerFnError::Args(plan.invalid.join(", ")));
+  }
+
+  let left_date = Local::now().to_string();
+  let departures = persons
+    .into_iter()
+    .filter(|person| plan.changes.deleted.contains(&person.uuid))
+    .map(|person| Departure::new(person, left_date.clone()))
+    .collect();
+  match apply_bulk_changes(plan.changes.updated.clone(), departures).await {
+    Ok(()) => Ok(plan.changes),
+    Err(person_error) => Err(ServerFnError::Response(ErrorMessage::create(person_error))),
   }
 }
 
 cfg_if::cfg_if! {
   if #[cfg(feature = "ssr")] {
-    use super::{get_all_persons, add_person, delete_person, update_person, PersonError};
-    use chrono::{DateTime, Local};
+    use super::{get_all_persons, get_person, add_person, add_persons, apply_bulk_changes, get_all_departures, get_dashboard_aggregates, update_person, Departure, PersonError, TEAM_PATH};
+    use super::bulk::BulkPlan;
+    use super::history::{date_of, members_on, parse_history_range, MAX_HISTORY_MONTHS};
+    use super::auth::{forbidden, require_user, CurrentUser};
+    use super::import::parse_import_csv;
+    use chrono::{DateTime, Local, NaiveDate};
     use uuid::Uuid;
+    use validator::Validate;
+
+    // a plain html form post (no wasm loaded yet) expects a page back, so send it to the team page
+    // with a flash message instead of leaving it on the referer with the modal still open
+    // calls made by the client side action get their result as usual
+    pub fn redirect_with_flash(toast_message_type: ToastMessageType) {
+      let accepts_html = use_context::<actix_web::HttpRequest>()
+        .and_then(|req| {
+          req.headers()
+            .get(actix_web::http::header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .map(|accept| accept.contains("text/html"))
+        })
+        .unwrap_or(false);
+
+      if let (true, Some(flash_key)) = (accepts_html, toast_message_type.flash_key()) {
+        leptos_actix::redirect(&format!("{}?flash={}", TEAM_PATH, flash_key));
+      }
+    }
+
+    fn parse_as_of(as_of: &str) -> Result<NaiveDate, ServerFnError> {
+      date_of(as_of).ok_or_else(|| ServerFnError::Args(String::from("the day must look like 2024-09-30")))
+    }
+
+    // current members and the ones who left, with the pay the user may not see redacted
+    pub async fn retrieve_redacted_team(current_user: &CurrentUser) -> (Vec<Person>, Vec<Departure>) {
+      let persons = retrieve_all_persons().await
+        .into_iter()
+        .map(|person| current_user.redact(person))
+        .collect();
+      let departures = get_all_departures().await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|departure| Departure::new(current_user.redact(departure.person), departure.left_date))
+        .collect();
+      (persons, departures)
+    }
 
     pub async fn retrieve_all_persons() -> Vec<Person> {
       let get_all_person_result = get_all_persons().await;
@@ -80,8 +284,25 @@ cfg_if::cfg_if! {
       }
     }
 
-    pub async fn add_new_person<T>(name: T, title: T, level: T, compensation: i32) -> Option<Person> where T: Into<String> {
-      
+    pub async fn find_team_person<T>(uuid: T) -> Result<Option<Person>, PersonError>
+    where T: Into<String> {
+      get_person(uuid.into()).await
+    }
+
+    pub async fn add_new_person<T>(name: T, title: T, level: T, compensation: i32, manager: Option<String>) -> Option<Person> where T: Into<String> {
+      add_person(new_team_person(name.into(), title.into(), level.into(), compensation).with_manager(manager)).await
+    }
+
+    // saved in one transaction, so either every member is created or none is
+    pub async fn import_team_persons(add_person_requests: Vec<AddPersonRequest>) -> Result<Vec<Person>, PersonError> {
+      let new_persons = add_person_requests
+        .into_iter()
+        .map(|request| new_team_person(request.name, request.title, request.level, request.compensation).with_manager(request.manager))
+        .collect();
+      add_persons(new_persons).await
+    }
+
+    fn new_team_person(name: String, title: String, level: String, compensation: i32) -> Person {
       let mut buffer = Uuid::encode_buffer();
       let uuid = Uuid::new_v4().simple().encode_lower(&mut buffer);
 
@@ -89,20 +310,26 @@ cfg_if::cfg_if! {
       let current_now = Local::now();
       let current_formatted = current_now.to_string();
 
-      let new_person = Person::new(String::from(uuid), name.into(), title.into(), level.into(), compensation, current_formatted);
-      
-      add_person(new_person).await
+      Person::new(String::from(uuid), name, title, level, compensation, current_formatted)
     }
 
+    // the member is kept as a departure from today, for reports on the months they were still here
     pub async fn delete_team_person<T>(uuid: T) -> Result<Option<Person>, PersonError>
     where T: Into<String> {
-      delete_person(uuid.into()).await
+      let Some(person) = get_person(uuid.into()).await? else {
+        return Ok(None);
+      };
+      let departure = Departure::new(person.clone(), Local::now().to_string());
+      apply_bulk_changes(Vec::new(), vec![departure])
+        .await
+        .map_err(|_| PersonError::PersonDeleteFailure)?;
+      Ok(Some(person))
     }
 
-    pub async fn edit_team_person<T>(uuid: T, title: T, level: T, compensation: i32) -> Result<Option<Person>, PersonError>
+    pub async fn edit_team_person<T>(uuid: T, title: T, level: T, compensation: i32, manager: Option<String>) -> Result<Option<Person>, PersonError>
     where T: Into<String> 
     {
-      update_person(uuid.into(), title.into(), level.into(), compensation).await
+      update_person(uuid.into(), title.into(), level.into(), compensation, manager).await
     }
   } 
 }
@@ -0,0 +1,188 @@
+use leptos::*;
+use chrono::Local;
+
+use crate::app::{
+  get_persons_srv, AddPersonSrv, BulkPlan, BulkResult, BulkUpdateSrv, DeletePersonSrv, EditPersonSrv, ImportPersonsSrv, ImportSummary, Person,
+};
+
+// uuid prefix for rows that were added optimistically and are still waiting on the server
+const PENDING_UUID_PREFIX: &str = "pending-";
+
+pub type PersonsResource = Resource<(), Result<Vec<Person>, ServerFnError>>;
+pub type AddPersonAction = Action<AddPersonSrv, Result<Person, ServerFnError>>;
+pub type EditPersonAction = Action<EditPersonSrv, Result<Person, ServerFnError>>;
+pub type DeletePersonAction = Action<DeletePersonSrv, Result<Person, ServerFnError>>;
+pub type ImportPersonsAction = Action<ImportPersonsSrv, Result<ImportSummary, ServerFnError>>;
+pub type BulkUpdateAction = Action<BulkUpdateSrv, Result<BulkResult, ServerFnError>>;
+
+// shared list of team members, provided as context by the `/team` routes
+// the add/edit/delete/import/bulk server actions live here so every form dispatching them keeps the list in sync:
+// a submission in flight is shown straight away and simply drops out again if the server call fails
+#[derive(Clone, Copy)]
+pub struct PersonStore {
+  persons: PersonsResource,
+  add_action: AddPersonAction,
+  edit_action: EditPersonAction,
+  delete_action: DeletePersonAction,
+  import_action: ImportPersonsAction,
+  bulk_action: BulkUpdateAction,
+}
+
+impl PersonStore {
+  pub fn new() -> PersonStore {
+    let persons = create_resource(
+      || (),
+      move |_| async move { get_persons_srv().await },
+    );
+    let add_action = create_server_action::<AddPersonSrv>();
+    let edit_action = create_server_action::<EditPersonSrv>();
+    let delete_action = create_server_action::<DeletePersonSrv>();
+    let import_action = create_server_action::<ImportPersonsSrv>();
+    let bulk_action = create_server_action::<BulkUpdateSrv>();
+
+    let person_store = PersonStore {
+      persons,
+      add_action,
+      edit_action,
+      delete_action,
+      import_action,
+      bulk_action,
+    };
+
+    // once the server confirms a change we write it into the loaded list
+    on_action_success(add_action, move |created_person| {
+      person_store.mutate(|persons| {
+        if !persons.iter().any(|person| person.uuid == created_person.uuid) {
+          // newest members come first, same as the db ordering
+          persons.insert(0, created_person);
+        }
+      })
+    });
+    on_action_success(edit_action, move |updated_person| {
+      person_store.mutate(|persons| {
+        if let Some(person) = persons.iter_mut().find(|person| person.uuid == updated_person.uuid) {
+          *person = updated_person;
+        }
+      })
+    });
+    on_action_success(delete_action, move |deleted_person| {
+      person_store.mutate(|persons| persons.retain(|person| person.uuid != deleted_person.uuid))
+    });
+    // imported members are only known once saved, so there is nothing to show while in flight
+    on_action_success(import_action, move |import_summary| {
+      person_store.mutate(|persons| {
+        persons.splice(0..0, import_summary.imported);
+      })
+    });
+    on_action_success(bulk_action, move |bulk_result| {
+      person_store.mutate(|persons| bulk_result.apply_to_list(persons))
+    });
+
+    person_store
+  }
+
+  // read the members with any in-flight submissions applied on top
+  pub fn persons(&self) -> Option<Result<Vec<Person>, ServerFnError>> {
+    let add_input = self.add_action.input().get();
+    let edit_input = self.edit_action.input().get();
+    let delete_input = self.delete_action.input().get();
+    let bulk_input = self.bulk_action.input().get();
+
+    self.persons.get().map(|data| {
+      data.map(|mut persons| {
+        if let Some(AddPersonSrv { add_person_request }) = add_input {
+          persons.insert(0, Person::new(
+            format!("{}{}", PENDING_UUID_PREFIX, self.add_action.version().get_untracked()),
+            add_person_request.name,
+            add_person_request.title,
+            add_person_request.level,
+            add_person_request.compensation,
+            Local::now().to_string(),
+          ).with_manager(add_person_request.manager));
+        }
+        if let Some(EditPersonSrv { edit_person_request }) = edit_input {
+          if let Some(person) = persons.iter_mut().find(|person| person.uuid == edit_person_request.uuid) {
+            person.title = edit_person_request.title;
+            person.level = edit_person_request.level;
+            person.compensation = Some(edit_person_request.compensation);
+            *person = person.clone().with_manager(edit_person_request.manager);
+          }
+        }
+        if let Some(DeletePersonSrv { delete_person_request }) = delete_input {
+          persons.retain(|person| person.uuid != delete_person_request.uuid);
+        }
+        if let Some(BulkUpdateSrv { bulk_request }) = bulk_input {
+          BulkPlan::new(&persons, &bulk_request).changes.apply_to_list(&mut persons);
+        }
+        persons
+      })
+    })
+  }
+
+  pub fn is_pending(person: &Person) -> bool {
+    person.uuid.starts_with(PENDING_UUID_PREFIX)
+  }
+
+  pub fn add_action(&self) -> AddPersonAction {
+    self.add_action
+  }
+
+  pub fn edit_action(&self) -> EditPersonAction {
+    self.edit_action
+  }
+
+  pub fn delete_action(&self) -> DeletePersonAction {
+    self.delete_action
+  }
+
+  pub fn import_action(&self) -> ImportPersonsAction {
+    self.import_action
+  }
+
+  pub fn bulk_action(&self) -> BulkUpdateAction {
+    self.bulk_action
+  }
+
+  // apply a change to the loaded list, does nothing if the list has not loaded or failed
+  fn mutate(&self, f: impl FnOnce(&mut Vec<Person>)) {
+    self.persons.update(|data| {
+      if let Some(Ok(persons)) = data {
+        f(persons);
+      }
+    });
+  }
+}
+
+impl Default for PersonStore {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// runs `f` with the returned value each time the action completes successfully
+pub fn on_action_success<I: 'static, O: Clone + 'static>(
+  action: Action<I, Result<O, ServerFnError>>,
+  f: impl Fn(O) + 'static,
+) {
+  create_effect(move |last_version: Option<usize>| {
+    let version = action.version().get();
+    if last_version.is_some_and(|last_version| last_version != version) {
+      if let Some(Ok(output)) = action.value().get_untracked() {
+        f(output);
+      }
+    }
+    version
+  });
+}
+
+// the one place the store is created, wrapping the `/team` routes
+// it must sit inside `<Router>` so the actions can pick up errors from plain form posts
+#[component]
+pub fn PersonStoreProvider(children: Children) -> impl IntoView {
+  provide_context(PersonStore::new());
+  children()
+}
+
+pub fn use_person_store() -> PersonStore {
+  expect_context::<PersonStore>()
+}
@@ -7,10 +7,35 @@ const TOAST_PARENT_APPEAR_STYLE: &str = "flex flex-row top-0 h-16 w-full max-w-[
 
 const TOAST_STYLE: &str = "flex w-96 h-16 bg-[#333333] rounded px-10 py-4 text-blue-500 -mt-36 items-center transition-all duration-1000 ease-in-out"; 
 
+#[derive(Clone, Copy)]
 pub enum ToastMessageType {
   NewMemberAdded,
   MemberDeleted,
   MemberUpdated,
+  MembersImported { imported: usize, skipped: usize },
+  BulkApplied { updated: usize, deleted: usize },
+}
+
+impl ToastMessageType {
+  // short key carried in the `flash` query param when a plain form post redirects back
+  // none for messages with counts, those only come from client side submissions
+  pub fn flash_key(&self) -> Option<&'static str> {
+    match self {
+      ToastMessageType::NewMemberAdded => Some("member_added"),
+      ToastMessageType::MemberDeleted => Some("member_deleted"),
+      ToastMessageType::MemberUpdated => Some("member_updated"),
+      ToastMessageType::MembersImported { .. } | ToastMessageType::BulkApplied { .. } => None,
+    }
+  }
+
+  pub fn from_flash_key(flash_key: &str) -> Option<ToastMessageType> {
+    match flash_key {
+      "member_added" => Some(ToastMessageType::NewMemberAdded),
+      "member_deleted" => Some(ToastMessageType::MemberDeleted),
+      "member_updated" => Some(ToastMessageType::MemberUpdated),
+      _ => None,
+    }
+  }
 }
 
 pub type ToastMessage = String;
@@ -25,6 +50,12 @@ impl Toast for ToastMessage{
       ToastMessageType::NewMemberAdded => String::from("New member added"),
       ToastMessageType::MemberUpdated => String::from("Existing member updated"),
       ToastMessageType::MemberDeleted => String::from("Existing member Deleted"),
+      ToastMessageType::MembersImported { imported, skipped: 0 } => format!("{} members imported", imported),
+      ToastMessageType::MembersImported { imported, skipped } => {
+        format!("{} members imported, {} rows skipped", imported, skipped)
+      }
+      ToastMessageType::BulkApplied { updated, deleted: 0 } => format!("{} members updated", updated),
+      ToastMessageType::BulkApplied { deleted, .. } => format!("{} members deleted", deleted),
     }
   }
 }
@@ -12,6 +12,11 @@ async fn main() -> std::io::Result<()> {
     // Generate the list of routes in your Leptos App
     let routes = generate_route_list(App);
     println!("listening on http://{}", &addr);
+    // one db connection and sign in for the whole server
+    database::open_db_connection().await;
+    auth::create_initial_user().await;
+    // shared by all workers so the counts are per server, not per thread
+    let rate_limits = web::Data::new(rate_limit::RateLimits::from_env());
 
     HttpServer::new(move || {
         let leptos_options = &conf.leptos_options;
@@ -24,8 +29,34 @@ async fn main() -> std::io::Result<()> {
             .service(Files::new("/assets", site_root))
             // serve the favicon from /favicon.ico
             .service(favicon)
+            // versioned json api for scripts, the server functions stay under /api
+            .service(web::scope(rest_api::API_V1_PATH).configure(rest_api::configure))
+            // openapi document of that api and a docs page rendered from it
+            .service(openapi::openapi_json)
+            .service(openapi::api_docs)
+            // csv/json download of the roster
+            .service(export::export_persons)
+            // svg/png download of a dashboard chart
+            .service(chart_export::export_chart)
+            // printable team report of a period and the same report as a pdf
+            .service(report::report_page)
+            .service(report::report_pdf_download)
+            // single sign-on through the company identity provider, when configured
+            .service(oidc::oidc_login)
+            .service(oidc::oidc_callback)
+            // graphql for internal tools
+            .configure(graphql::configure)
             .leptos_routes(leptos_options.to_owned(), routes.to_owned(), App)
             .app_data(web::Data::new(leptos_options.to_owned()))
+            .app_data(rate_limits.clone())
+            // request rate and body size, counted once the session has told who is calling
+            .wrap(middleware::from_fn(rate_limit::limit_requests))
+            // everything except the login page and static files needs a session
+            .wrap(middleware::from_fn(auth::require_session))
+            // posts from other sites are turned away before anything else runs
+            .wrap(middleware::from_fn(csrf::require_csrf))
+            // no third-party scripts, pages add a nonce for their own inline ones
+            .wrap(middleware::from_fn(csp::default_content_security_policy))
         //.wrap(middleware::Compress::default())
     })
     .bind(&addr)?